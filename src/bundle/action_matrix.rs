@@ -0,0 +1,205 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::core::{Enso, Result};
+
+use super::actions::Action;
+
+/// The actions supported by each protocol, with their protocol-specific inputs.
+///
+/// Only the pairs of the matrix are accepted by the API, e.g. to build a bundle.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+/// let matrix = enso.get_protocol_action_matrix(["aave-v3", "lido"]).await?;
+/// assert!(matrix.supports("aave-v3", "borrow"));
+/// assert!(!matrix.supports("lido", "borrow"));
+/// println!("{}", matrix);
+/// // protocol borrow deposit redeem
+/// // aave-v3       x       x      x
+/// // lido          -       x      -
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ProtocolActionMatrix {
+    actions: BTreeMap<String, Vec<Action>>,
+}
+
+impl ProtocolActionMatrix {
+    /// Creates an empty matrix.
+    pub fn new() -> ProtocolActionMatrix {
+        ProtocolActionMatrix::default()
+    }
+
+    /// Returns the number of protocols of the matrix.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns `true` if the matrix has no protocols.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Sets the actions of the protocol `slug`, returning the previous ones.
+    pub fn insert<T: ToString>(&mut self, slug: T, actions: Vec<Action>) -> Option<Vec<Action>> {
+        self.actions.insert(slug.to_string(), actions)
+    }
+
+    /// Returns `true` if the actions of the protocol `slug` are known.
+    pub fn contains(&self, slug: &str) -> bool {
+        self.actions.contains_key(slug)
+    }
+
+    /// Returns the slugs of the protocols, in alphabetical order.
+    pub fn slugs(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    /// Returns the actions of the protocol `slug`, or `None` if unknown.
+    pub fn actions(&self, slug: &str) -> Option<&[Action]> {
+        self.actions.get(slug).map(Vec::as_slice)
+    }
+
+    /// Returns the action named `action` of the protocol `slug`, with the inputs
+    /// specific to the protocol.
+    pub fn action(&self, slug: &str, action: &str) -> Option<&Action> {
+        self.actions(slug)?.iter().find(|a| a.action == action)
+    }
+
+    /// Returns `true` if the protocol `slug` supports the action named `action`.
+    pub fn supports(&self, slug: &str, action: &str) -> bool {
+        self.action(slug, action).is_some()
+    }
+
+    /// Returns the slugs of the protocols supporting the action named `action`.
+    pub fn protocols_supporting(&self, action: &str) -> Vec<&str> {
+        self.slugs()
+            .filter(|slug| self.supports(slug, action))
+            .collect()
+    }
+
+    /// Returns the names of the actions supported by at least one protocol.
+    pub fn action_names(&self) -> BTreeSet<&str> {
+        self.actions
+            .values()
+            .flatten()
+            .map(|action| action.action.as_str())
+            .collect()
+    }
+}
+
+impl Display for ProtocolActionMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = self.action_names();
+        let width = self.slugs().map(str::len).chain(["protocol".len()]).max();
+        let width = width.unwrap_or_default();
+        write!(f, "{:<width$}", "protocol")?;
+        for name in &names {
+            write!(f, " {}", name)?;
+        }
+        writeln!(f)?;
+        for slug in self.slugs() {
+            write!(f, "{:<width$}", slug)?;
+            for name in &names {
+                let mark = if self.supports(slug, name) { "x" } else { "-" };
+                write!(f, " {:>width$}", mark, width = name.len())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Enso {
+    /// Retrieves the actions supported by each of the protocols `slugs`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ProtocolActionMatrix` of the protocols, or the
+    /// first `EnsoError` met.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::chains::Chain;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let protocols = enso.get_protocols_by_chain(Chain::Arbitrum.id()).await?;
+    /// let matrix = enso
+    ///     .get_protocol_action_matrix(protocols.iter().map(|p| p.slug.as_str()))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_protocol_action_matrix<I, S>(&self, slugs: I) -> Result<ProtocolActionMatrix>
+    where
+        I: IntoIterator<Item = S>,
+        S: ToString,
+    {
+        stream::iter(slugs.into_iter().map(|slug| slug.to_string()))
+            .map(|slug| async move {
+                let actions = self.get_protocol_actions(&slug).await?;
+                Ok((slug, actions))
+            })
+            .buffer_unordered(4)
+            .try_fold(
+                ProtocolActionMatrix::new(),
+                |mut matrix, (slug, actions)| async move {
+                    matrix.insert(slug, actions);
+                    Ok(matrix)
+                },
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::MockServer;
+
+    #[tokio::test]
+    async fn test_protocol_action_matrix() {
+        let server = MockServer::start();
+        let enso = server.enso();
+
+        let matrix = enso
+            .get_protocol_action_matrix(["lido", "aave-v3", "curve"])
+            .await
+            .unwrap();
+
+        assert_eq!(matrix.len(), 3);
+        assert!(matrix.supports("aave-v3", "borrow"));
+        assert!(!matrix.supports("lido", "borrow"));
+        assert!(!matrix.supports("unknown", "deposit"));
+        assert_eq!(
+            matrix.protocols_supporting("redeem"),
+            vec!["aave-v3", "curve"]
+        );
+        assert_eq!(
+            matrix
+                .action("curve", "deposit")
+                .and_then(|action| action.inputs.iter().find(|(f, _)| f == "primaryAddress"))
+                .map(|(_, description)| description.as_str()),
+            Some("Address of the Curve pool")
+        );
+        assert_eq!(
+            matrix.to_string(),
+            "protocol borrow deposit redeem\n\
+             aave-v3       x       x      x\n\
+             curve         -       x      x\n\
+             lido          -       x      -\n"
+        );
+
+        assert!(enso
+            .get_protocol_action_matrix(["lido", "unknown"])
+            .await
+            .is_err());
+    }
+}
@@ -1,9 +1,7 @@
-use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
-use reqwest::{header::AUTHORIZATION, Client};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::core::Enso;
+use crate::core::{Endpoint, Enso, Result};
 
 pub static ACTION_CALL: Lazy<Action> = Lazy::new(|| Action {
     action: "call".to_string(),
@@ -24,7 +22,7 @@ pub static ACTION_CALL: Lazy<Action> = Lazy::new(|| Action {
     ],
 });
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Action {
     pub action: String,
@@ -37,31 +35,61 @@ impl Enso {
     ///
     /// # Returns
     ///
-    /// A `Result` containing a vector of `Action` instances or an error.
+    /// A `Result` containing a vector of `Action` instances or an `EnsoError`.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
     /// let actions = enso.get_actions().await?;
+    /// # Ok(())
+    /// # }
     /// ```
+    #[tracing::instrument(skip_all)]
     pub async fn get_actions(&self) -> Result<Vec<Action>> {
-        let client = Client::new();
-        let url = format!("{}/actions", self.get_api_url());
-        let auth = format!("Bearer {}", self.api_key);
-        let response = client.get(&url).header(AUTHORIZATION, auth).send().await;
-        response
-            .map_err(|_| anyhow!("Couldn't get tokens"))?
-            .json::<Vec<Action>>()
-            .await
-            .map_err(|_| anyhow!("Couldn't parse result"))
+        let path = Endpoint::Actions.path();
+        self.get_json(path, &[]).await
+    }
+
+    /// Retrieves the actions supported by the protocol `slug`, with the inputs
+    /// expected by that protocol.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a vector of `Action` instances or an `EnsoError`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let actions = enso.get_protocol_actions("aave-v3").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(skip(self))]
+    pub async fn get_protocol_actions(&self, slug: &str) -> Result<Vec<Action>> {
+        let path = Endpoint::Actions.path();
+        self.get_json(&format!("{}/{}", path, slug), &[]).await
     }
 }
 
 mod object_as_vector {
     use serde::de::Error;
-    use serde::Deserializer;
+    use serde::ser::SerializeMap;
+    use serde::{Deserializer, Serializer};
     use serde_json::Value;
 
+    pub fn serialize<S>(fields: &[(String, String)], ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = ser.serialize_map(Some(fields.len()))?;
+        for (f, v) in fields {
+            map.serialize_entry(f, v)?;
+        }
+        map.end()
+    }
+
     pub fn deserialize<'de, D>(des: D) -> Result<Vec<(String, String)>, D::Error>
     where
         D: Deserializer<'de>,
@@ -88,19 +116,27 @@ mod object_as_vector {
 
 #[cfg(test)]
 mod test {
-    use crate::core::Version;
-
-    use super::*;
+    use crate::testing::MockServer;
 
     #[tokio::test]
     async fn test_get_actions() {
-        let enso = Enso::new(
-            "1e02632d-6feb-4a75-a157-documentation".to_string(),
-            Version::V1,
-        );
+        let server = MockServer::start();
+        let enso = server.enso();
 
         let actions = enso.get_actions().await;
 
         assert!(actions.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_get_protocol_actions() {
+        let server = MockServer::start();
+        let enso = server.enso();
+
+        let actions = enso.get_protocol_actions("lido").await.unwrap();
+
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].action, "deposit");
+        assert!(enso.get_protocol_actions("unknown").await.is_err());
+    }
 }
@@ -1,9 +1,8 @@
-use anyhow::{anyhow, Result};
-use reqwest::header::AUTHORIZATION;
-use reqwest::Client;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Number, Value};
 
-use crate::core::Enso;
+use crate::core::{parse_json, Endpoint, Enso, Result};
 use crate::metadata::protocols::{Protocol, ENSO_PROTOCOL};
 
 use super::actions::{Action, ACTION_CALL};
@@ -27,6 +26,32 @@ pub struct Bundle {
     transactions: Vec<Transaction>,
 }
 
+/// Response of the API to a bundle, with the transaction executing it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleResponse {
+    /// The estimated gas of the transaction.
+    pub gas: String,
+    /// The block the bundle was built at.
+    pub created_at: u64,
+    /// The transaction to sign and send.
+    pub tx: BundleTransaction,
+}
+
+/// Transaction executing a bundle through the Enso router.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleTransaction {
+    /// The address sending the transaction.
+    pub from: String,
+    /// The address of the contract called, usually the Enso router.
+    pub to: String,
+    /// The calldata of the transaction.
+    pub data: String,
+    /// The amount of native currency sent, in wei.
+    pub value: String,
+}
+
 impl Bundle {
     /// Creates a new `Bundle` instance for the specified chain.
     ///
@@ -37,6 +62,7 @@ impl Bundle {
     /// # Example
     ///
     /// ```
+    /// # use enso::bundle::core::Bundle;
     /// let bundle = Bundle::new(1);
     /// ```
     pub fn new(chain_id: u32) -> Bundle {
@@ -81,14 +107,24 @@ impl Bundle {
     /// # Example
     ///
     /// ```
-    /// bundle.add_call(vec![ParamValue::Value(0)], vec![ParamValue::Array()]);
+    /// # use enso::bundle::core::{Bundle, ParamValue};
+    /// # let mut bundle = Bundle::new(1);
+    /// let value = |value: &str| ParamValue::Value(value.to_string());
+    /// bundle.add_call(
+    ///     vec![
+    ///         value("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"),
+    ///         value("approve"),
+    ///         value("function approve(address,uint256)"),
+    ///     ],
+    ///     vec![value("0x80eba3855878739f4710233a8a19d89bdd2ffb8e"), value("1000000")],
+    /// );
     /// ```
     pub fn add_call(&mut self, mut args: Vec<ParamValue>, abi_args: Vec<ParamValue>) {
         args.push(ParamValue::ValueArray(abi_args));
         self.add_action(ENSO_PROTOCOL.clone(), ACTION_CALL.clone(), args);
     }
 
-    fn to_json(&self) -> String {
+    fn to_value(&self) -> Value {
         fn output_of_call_at(tx: usize) -> Value {
             let mut object = Map::new();
             object.insert(
@@ -141,10 +177,10 @@ impl Bundle {
                 args.insert(name.clone(), param_value_to_json(value, current_tx));
             }
             tx.insert("args".to_owned(), Value::Object(args));
-            bundle.push(tx);
+            bundle.push(Value::Object(tx));
         }
 
-        serde_json::to_string(&bundle).unwrap()
+        Value::Array(bundle)
     }
 }
 
@@ -158,30 +194,30 @@ impl Enso {
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or failure of the operation.
+    /// A `Result` containing the `BundleResponse` with the transaction to sign, or
+    /// the `EnsoError` returned by the API.
     ///
     /// # Example
     ///
+    /// ```no_run
+    /// # use enso::{bundle::core::Bundle, metadata::chains::Chain};
+    /// # async fn example(enso: enso::core::Enso, bundle: Bundle) -> enso::core::Result<()> {
+    /// let response = enso.send_bundle(bundle, "0xYourAddress").await?;
+    /// println!("{:?}", Chain::Ethereum.address_url(&response.tx.to));
+    /// # Ok(())
+    /// # }
     /// ```
-    /// let result = enso.send_bundle(bundle, "0xYourAddress").await;
-    /// ```
-    pub async fn send_bundle(&self, bundle: Bundle, from_address: &str) -> Result<()> {
-        let client = Client::new();
-        let url = format!("{}/shortcuts/bundle", self.get_api_url());
-        let auth = format!("Bearer {}", self.api_key);
+    #[tracing::instrument(skip(self, bundle), fields(chain_id = bundle.chain_id))]
+    pub async fn send_bundle(&self, bundle: Bundle, from_address: &str) -> Result<BundleResponse> {
         let query = vec![
-            ("chainId", bundle.chain_id.to_string()),
-            ("fromAddress", from_address.to_owned()),
+            ("chainId".to_owned(), bundle.chain_id.to_string()),
+            ("fromAddress".to_owned(), from_address.to_owned()),
         ];
-        let response = client
-            .post(&url)
-            .header(AUTHORIZATION, auth)
-            .query(&query)
-            .json(&bundle.to_json())
-            .send()
-            .await;
-        let _ = response.map_err(|_| anyhow!("Couldn't send transaction"))?;
-        Ok(())
+        let path = Endpoint::Bundle.path();
+        let body = self
+            .send(Method::POST, path, &query, Some(&bundle.to_value()))
+            .await?;
+        parse_json(&body)
     }
 }
 
@@ -189,7 +225,10 @@ impl Enso {
 mod test {
     use once_cell::sync::Lazy;
 
-    use crate::core::Version;
+    use crate::{
+        metadata::chains::{Chain, ENSO_ROUTER_ADDRESS},
+        testing::MockServer,
+    };
 
     use super::*;
 
@@ -263,21 +302,26 @@ mod test {
         let bundle = create_bundle(1);
 
         let original: Value = serde_json::from_str(JSON).unwrap();
-        let bundle: Value = serde_json::from_str(&bundle.to_json()).unwrap();
 
-        assert_eq!(bundle, original);
+        assert_eq!(bundle.to_value(), original);
     }
 
     #[tokio::test]
     async fn test_send_bundle() {
-        let enso = Enso::new(
-            "1e02632d-6feb-4a75-a157-documentation".to_string(),
-            Version::V1,
-        );
+        let server = MockServer::start();
+        let enso = server.enso();
         let bundle = create_bundle(1);
         let from_address = "0xd8da6bf26964af9d7eed9e03e53415d37aa96045";
-        let result = enso.send_bundle(bundle, from_address).await;
+        let response = enso.send_bundle(bundle, from_address).await.unwrap();
 
-        assert!(result.is_ok());
+        assert_eq!(response.tx.from, from_address);
+        assert_eq!(response.tx.to, ENSO_ROUTER_ADDRESS);
+        assert_eq!(
+            Chain::Ethereum.address_url(&response.tx.to),
+            Some(format!(
+                "https://etherscan.io/address/{}",
+                ENSO_ROUTER_ADDRESS
+            ))
+        );
     }
 }
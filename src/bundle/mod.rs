@@ -1,2 +1,3 @@
+pub mod action_matrix;
 pub mod actions;
 pub mod core;
@@ -3,6 +3,12 @@ use serde::Deserialize;
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub api_key: String,
+    #[serde(default = "default_cache_dir")]
+    pub cache_dir: String,
+}
+
+fn default_cache_dir() -> String {
+    ".enso-cache".to_string()
 }
 
 impl Default for Config {
@@ -0,0 +1,334 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, Proxy,
+};
+
+use super::{
+    CassetteMode, CassetteTransport, Enso, EnsoError, EnsoInner, HttpTransport, RateLimiter,
+    ReqwestTransport, Result, RetryPolicy, Version, API_ADDRESS,
+};
+
+const USER_AGENT: &str = concat!("enso-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Builder used to configure and create an [`Enso`] client.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use enso::core::Enso;
+/// # fn example() -> enso::core::Result<()> {
+/// let enso = Enso::builder()
+///     .api_key("your_api_key")
+///     .base_url("http://localhost:8080")
+///     .timeout(Duration::from_secs(10))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct EnsoBuilder {
+    api_key: Option<String>,
+    version: Version,
+    base_url: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: String,
+    default_headers: Vec<(String, String)>,
+    proxy: Option<String>,
+    no_proxy: bool,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    client: Option<Client>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    cassette: Option<(PathBuf, CassetteMode)>,
+    retry_policy: RetryPolicy,
+    rate_limit: Option<(f64, u32)>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Default for EnsoBuilder {
+    fn default() -> Self {
+        EnsoBuilder {
+            api_key: None,
+            version: Version::V1,
+            base_url: API_ADDRESS.to_string(),
+            timeout: None,
+            connect_timeout: None,
+            user_agent: USER_AGENT.to_string(),
+            default_headers: Vec::new(),
+            proxy: None,
+            no_proxy: false,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            client: None,
+            transport: None,
+            cassette: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limit: None,
+            rate_limiter: None,
+        }
+    }
+}
+
+impl EnsoBuilder {
+    /// Sets the API key sent as a bearer token on every request.
+    pub fn api_key<T: ToString>(mut self, api_key: T) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Sets the API version to use. Defaults to `Version::V1`.
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the base address of the API, e.g. a staging host or a local mock.
+    ///
+    /// Defaults to `https://api.enso.finance`.
+    pub fn base_url<T: ToString>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.to_string();
+        self
+    }
+
+    /// Sets the total timeout applied to each request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    pub fn user_agent<T: ToString>(mut self, user_agent: T) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    /// Adds a header sent with every request.
+    ///
+    /// Invalid names or values are reported by [`EnsoBuilder::build`].
+    pub fn default_header<K: ToString, V: ToString>(mut self, name: K, value: V) -> Self {
+        self.default_headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Routes every request through the given proxy URL.
+    pub fn proxy<T: ToString>(mut self, proxy: T) -> Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    /// Disables any proxy, including the ones set by environment variables.
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Sets how long idle connections are kept in the pool.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables TCP keepalive on the pooled connections.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Uses an existing `reqwest::Client` instead of creating a new one, sharing its
+    /// connection pool with the rest of the application.
+    ///
+    /// When set, the timeouts, user agent, default headers, proxy and pool settings
+    /// of this builder are ignored in favor of the ones of the given client.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sends requests through a custom `HttpTransport`, e.g. an `InMemoryTransport`
+    /// returning canned responses.
+    ///
+    /// When set, the HTTP settings of this builder, including
+    /// [`EnsoBuilder::http_client`], are ignored.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Records every interaction to the cassette at `path`, or replays them from it,
+    /// depending on `mode`. See [`CassetteTransport`].
+    ///
+    /// When recording, requests go through the transport configured on this builder.
+    /// When replaying, the transport settings are ignored and the cassette must exist.
+    pub fn cassette<P: Into<PathBuf>>(mut self, path: P, mode: CassetteMode) -> Self {
+        self.cassette = Some((path.into(), mode));
+        self
+    }
+
+    /// Sets how failed requests are retried. Defaults to `RetryPolicy::default()`,
+    /// which only retries idempotent requests.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Limits the client to `requests_per_second` on average, with bursts of up to
+    /// `burst` requests. Invalid values are reported by [`EnsoBuilder::build`].
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limit = Some((requests_per_second, burst));
+        self
+    }
+
+    /// Uses an existing `RateLimiter`, sharing its quota with other clients.
+    /// Takes precedence over [`EnsoBuilder::rate_limit`].
+    pub fn rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Creates the `Enso` client.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the configured `Enso` or an error if the API key is missing
+    /// or any of the settings is invalid.
+    pub fn build(self) -> Result<Enso> {
+        let transport: Arc<dyn HttpTransport> = match (&self.transport, &self.client) {
+            (Some(transport), _) => transport.clone(),
+            (None, Some(client)) => Arc::new(ReqwestTransport::new(client.clone())),
+            (None, None) => Arc::new(ReqwestTransport::new(self.build_client()?)),
+        };
+        let transport: Arc<dyn HttpTransport> = match &self.cassette {
+            Some((path, CassetteMode::Record)) => {
+                Arc::new(CassetteTransport::record(path, transport))
+            }
+            Some((path, CassetteMode::Replay)) => Arc::new(CassetteTransport::replay(path)?),
+            None => transport,
+        };
+        let api_key = self
+            .api_key
+            .ok_or_else(|| EnsoError::Config("Missing API key".to_string()))?;
+        let rate_limiter = match (self.rate_limiter, self.rate_limit) {
+            (Some(limiter), _) => Some(limiter),
+            (None, Some((requests_per_second, burst))) => {
+                Some(Arc::new(RateLimiter::new(requests_per_second, burst)?))
+            }
+            (None, None) => None,
+        };
+
+        Ok(Enso {
+            inner: Arc::new(EnsoInner {
+                api_address: self.base_url.trim_end_matches('/').to_string(),
+                api_key,
+                version: self.version,
+                transport,
+                retry_policy: self.retry_policy,
+                rate_limiter,
+            }),
+        })
+    }
+
+    fn build_client(&self) -> Result<Client> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.default_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| EnsoError::Config(format!("Invalid header name: {}", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| EnsoError::Config(format!("Invalid value for header {}", name)))?;
+            headers.insert(name, value);
+        }
+
+        let mut client = Client::builder()
+            .user_agent(self.user_agent.as_str())
+            .default_headers(headers)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            client = client.pool_idle_timeout(timeout);
+        }
+        if self.no_proxy {
+            client = client.no_proxy();
+        } else if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|_| EnsoError::Config(format!("Invalid proxy: {}", proxy)))?;
+            client = client.proxy(proxy);
+        }
+        client.build().map_err(|e| EnsoError::Config(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let enso = Enso::builder()
+            .api_key("key")
+            .base_url("http://localhost:8080/")
+            .timeout(Duration::from_secs(5))
+            .connect_timeout(Duration::from_secs(1))
+            .user_agent("test")
+            .default_header("x-test", "1")
+            .build()
+            .unwrap();
+
+        assert_eq!(enso.get_api_url(), "http://localhost:8080/api/v1");
+    }
+
+    #[test]
+    fn test_build_errors() {
+        assert!(Enso::builder().build().is_err());
+        assert!(Enso::builder()
+            .api_key("key")
+            .default_header("invalid header", "1")
+            .build()
+            .is_err());
+        assert!(Enso::builder()
+            .api_key("key")
+            .proxy("not a url")
+            .build()
+            .is_err());
+        assert!(Enso::builder()
+            .api_key("key")
+            .rate_limit(0.0, 1)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_clones_share_client() {
+        fn assert_send_sync<T: Clone + Send + Sync + 'static>() {}
+        assert_send_sync::<Enso>();
+
+        let enso = Enso::builder().api_key("key").build().unwrap();
+        let clone = enso.clone();
+
+        assert!(Arc::ptr_eq(&enso.inner, &clone.inner));
+    }
+}
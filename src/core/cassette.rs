@@ -0,0 +1,421 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{value::RawValue, Value};
+
+use super::{EnsoError, HttpRequest, HttpResponse, HttpTransport, Result};
+
+/// Whether a [`CassetteTransport`] records or replays interactions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Sends requests through the wrapped transport and writes every interaction to
+    /// the cassette file.
+    Record,
+    /// Answers requests from the cassette file without touching the network.
+    Replay,
+}
+
+/// [`HttpTransport`] recording request/response pairs to a JSON cassette file and
+/// replaying them later.
+///
+/// Interactions are keyed by method, path, query parameters (in any order) and
+/// JSON body. When the same request was recorded several times, the responses are
+/// replayed in order, the last one being repeated. Request headers aren't stored,
+/// so the API key never ends up in the cassette.
+///
+/// A recording transport writes the cassette file when it's dropped, including
+/// while a panicking test unwinds, or earlier with [`CassetteTransport::save`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::core::{CassetteMode, Enso};
+/// # async fn example() -> enso::core::Result<()> {
+/// // Record once against the real API...
+/// let enso = Enso::builder()
+///     .api_key("your_api_key")
+///     .cassette("tests/cassettes/tokens.json", CassetteMode::Record)
+///     .build()?;
+/// let tokens = enso.get_tokens(&[("chainId", "1")]).await?;
+///
+/// // ...then replay forever offline.
+/// let enso = Enso::builder()
+///     .api_key("any")
+///     .cassette("tests/cassettes/tokens.json", CassetteMode::Replay)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CassetteTransport {
+    path: PathBuf,
+    inner: Option<Arc<dyn HttpTransport>>,
+    interactions: Mutex<Vec<Interaction>>,
+    replayed: Mutex<Vec<usize>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedRequest {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Body::is_empty")]
+    body: Body,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Body::is_empty")]
+    body: Body,
+}
+
+/// A body stored as JSON when possible, to keep cassettes readable and diffable.
+///
+/// JSON is stored verbatim, so a replayed body has the exact bytes of the recorded
+/// one, whatever its key order or number precision.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum Body {
+    #[default]
+    Empty,
+    Json(Box<RawValue>),
+    Text(String),
+}
+
+impl Body {
+    fn new(bytes: &[u8]) -> Body {
+        if bytes.is_empty() {
+            return Body::Empty;
+        }
+        let text = String::from_utf8_lossy(bytes);
+        match serde_json::from_str::<Box<RawValue>>(&text) {
+            // Surrounding whitespace would be lost, so such bodies are kept as text.
+            Ok(json) if json.get() == text => Body::Json(json),
+            _ => Body::Text(text.into_owned()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Body::Empty)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Body::Empty => Vec::new(),
+            Body::Json(json) => json.get().as_bytes().to_vec(),
+            Body::Text(text) => text.clone().into_bytes(),
+        }
+    }
+}
+
+/// JSON bodies are compared by value, so a request matches whatever its key order.
+impl PartialEq for Body {
+    fn eq(&self, other: &Body) -> bool {
+        match (self, other) {
+            (Body::Empty, Body::Empty) => true,
+            (Body::Json(a), Body::Json(b)) => {
+                serde_json::from_str::<Value>(a.get()).ok()
+                    == serde_json::from_str::<Value>(b.get()).ok()
+            }
+            (Body::Text(a), Body::Text(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl RecordedRequest {
+    fn new(request: &HttpRequest) -> RecordedRequest {
+        let mut query = request.query.clone();
+        query.sort();
+        RecordedRequest {
+            method: request.method.to_string(),
+            path: request.path(),
+            query,
+            body: Body::new(request.body.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+impl CassetteTransport {
+    /// Creates a transport sending requests through `inner` and recording them to
+    /// the cassette at `path`, which is overwritten.
+    pub fn record<P: AsRef<Path>>(path: P, inner: Arc<dyn HttpTransport>) -> CassetteTransport {
+        CassetteTransport {
+            path: path.as_ref().to_path_buf(),
+            inner: Some(inner),
+            interactions: Mutex::new(Vec::new()),
+            replayed: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Creates a transport replaying the cassette at `path`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the transport, or an `EnsoError::Cassette` if the file
+    /// can't be read or isn't a valid cassette.
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<CassetteTransport> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(|e| EnsoError::Cassette(format!("Couldn't read {}: {}", path.display(), e)))?;
+        let interactions = serde_json::from_str::<Vec<Interaction>>(&content).map_err(|e| {
+            EnsoError::Cassette(format!("Invalid cassette {}: {}", path.display(), e))
+        })?;
+        Ok(CassetteTransport {
+            path: path.to_path_buf(),
+            inner: None,
+            replayed: Mutex::new(vec![0; interactions.len()]),
+            interactions: Mutex::new(interactions),
+        })
+    }
+
+    /// Returns whether this transport records or replays interactions.
+    pub fn mode(&self) -> CassetteMode {
+        match self.inner {
+            Some(_) => CassetteMode::Record,
+            None => CassetteMode::Replay,
+        }
+    }
+
+    /// Returns the path of the cassette file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of interactions in the cassette.
+    pub fn len(&self) -> usize {
+        self.interactions.lock().unwrap().len()
+    }
+
+    /// Returns `true` if the cassette doesn't hold any interaction.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the interactions recorded so far to the cassette file.
+    ///
+    /// This is done when the transport is dropped, so it's only needed to handle
+    /// write errors, which are otherwise logged. The write blocks the thread.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Ok` if the file was written, or an `EnsoError::Cassette`.
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&*self.interactions.lock().unwrap())
+            .map_err(|e| EnsoError::Cassette(e.to_string()))?;
+        fs::write(&self.path, content).map_err(|e| {
+            EnsoError::Cassette(format!("Couldn't write {}: {}", self.path.display(), e))
+        })
+    }
+
+    async fn record_interaction(
+        &self,
+        inner: &dyn HttpTransport,
+        request: HttpRequest,
+    ) -> Result<HttpResponse> {
+        let recorded = RecordedRequest::new(&request);
+        let response = inner.send(request).await?;
+
+        self.interactions.lock().unwrap().push(Interaction {
+            request: recorded,
+            response: RecordedResponse {
+                status: response.status,
+                headers: response.headers.clone(),
+                body: Body::new(&response.body),
+            },
+        });
+        Ok(response)
+    }
+
+    fn replay_interaction(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let recorded = RecordedRequest::new(request);
+        let interactions = self.interactions.lock().unwrap();
+        let matches = interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.request == recorded)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let mut replayed = self.replayed.lock().unwrap();
+        let index = matches
+            .iter()
+            .find(|index| replayed[**index] == 0)
+            .or(matches.last())
+            .copied()
+            .ok_or_else(|| {
+                EnsoError::Cassette(format!(
+                    "No interaction recorded in {} for {} {} {:?}",
+                    self.path.display(),
+                    recorded.method,
+                    recorded.path,
+                    recorded.query
+                ))
+            })?;
+        replayed[index] += 1;
+
+        let response = &interactions[index].response;
+        Ok(HttpResponse {
+            status: response.status,
+            headers: response.headers.clone(),
+            body: response.body.to_bytes(),
+        })
+    }
+}
+
+impl HttpTransport for CassetteTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse>> {
+        Box::pin(async move {
+            match &self.inner {
+                Some(inner) => self.record_interaction(inner.as_ref(), request).await,
+                None => self.replay_interaction(&request),
+            }
+        })
+    }
+}
+
+impl Drop for CassetteTransport {
+    fn drop(&mut self) {
+        if self.mode() == CassetteMode::Record {
+            if let Err(e) = self.save() {
+                tracing::warn!(error = %e, "Couldn't save cassette");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use reqwest::Method;
+
+    use crate::{
+        bundle::core::Bundle,
+        core::{Enso, InMemoryTransport, RetryPolicy},
+        testing::MockServer,
+    };
+
+    use super::*;
+
+    fn cassette_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("enso-{}-{}.json", name, std::process::id()))
+    }
+
+    fn replay_enso(path: &Path) -> Enso {
+        Enso::builder()
+            .api_key("any")
+            .cassette(path, CassetteMode::Replay)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = cassette_path("record-replay");
+        let recorded = {
+            let server = MockServer::start();
+            let enso = Enso::builder()
+                .api_key(crate::testing::API_KEY)
+                .base_url(server.url())
+                .cassette(&path, CassetteMode::Record)
+                .build()
+                .unwrap();
+            let tokens = enso
+                .tokens_stream(&[("chainId", "10")])
+                .collect::<Vec<_>>()
+                .await;
+            enso.send_bundle(Bundle::new(10), "0xabc").await.unwrap();
+            tokens
+                .into_iter()
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .concat()
+        };
+
+        // The server is gone, so every response comes from the cassette.
+        let enso = replay_enso(&path);
+        let replayed = enso
+            .tokens_stream(&[("chainId", "10")])
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .concat();
+        assert_eq!(replayed, recorded);
+        assert!(enso.send_bundle(Bundle::new(10), "0xabc").await.is_ok());
+        assert!(!fs::read_to_string(&path)
+            .unwrap()
+            .contains(crate::testing::API_KEY));
+
+        _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_replay_miss() {
+        let path = cassette_path("replay-miss");
+        fs::write(&path, "[]").unwrap();
+        let enso = Enso::builder()
+            .api_key("any")
+            .cassette(&path, CassetteMode::Replay)
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            enso.get_networks().await,
+            Err(EnsoError::Cassette(_))
+        ));
+        assert!(Enso::builder()
+            .api_key("any")
+            .cassette(cassette_path("missing"), CassetteMode::Replay)
+            .build()
+            .is_err());
+
+        _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_replay_exact_bytes() {
+        let path = cassette_path("exact-bytes");
+        let body = r#"{"z": 1, "a": 123456789012345678901234567890}"#;
+        let recorder = CassetteTransport::record(
+            &path,
+            Arc::new(InMemoryTransport::new().on(
+                Method::GET,
+                "/api/v1/networks",
+                HttpResponse::new(200, body),
+            )),
+        );
+        let request = HttpRequest {
+            method: Method::GET,
+            url: "https://api.enso.finance/api/v1/networks".to_string(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+        };
+        recorder.send(request.clone()).await.unwrap();
+        drop(recorder);
+
+        let replayer = CassetteTransport::replay(&path).unwrap();
+        let response = replayer.send(request).await.unwrap();
+        assert_eq!(String::from_utf8(response.body).unwrap(), body);
+
+        _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+/// Endpoints of the Enso API.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Endpoint {
+    Networks,
+    Protocols,
+    Tokens,
+    Actions,
+    Bundle,
+    Prices,
+    Balances,
+}
+
+impl Endpoint {
+    /// Returns the path of the endpoint, relative to the versioned API URL.
+    pub(crate) fn path(&self) -> &'static str {
+        match self {
+            Endpoint::Networks => "networks",
+            Endpoint::Protocols => "protocols",
+            Endpoint::Tokens => "tokens",
+            Endpoint::Actions => "actions",
+            Endpoint::Bundle => "shortcuts/bundle",
+            Endpoint::Prices => "prices",
+            Endpoint::Balances => "wallet/balances",
+        }
+    }
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Endpoint::Networks => "networks",
+                Endpoint::Protocols => "protocols",
+                Endpoint::Tokens => "tokens",
+                Endpoint::Actions => "actions",
+                Endpoint::Bundle => "bundle",
+                Endpoint::Prices => "prices",
+                Endpoint::Balances => "balances",
+            }
+        )
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+/// Result type returned by every `Enso` method.
+pub type Result<T> = std::result::Result<T, EnsoError>;
+
+/// Errors returned by the Enso client.
+#[derive(Debug, Error)]
+pub enum EnsoError {
+    /// The request couldn't be sent or the response couldn't be read.
+    #[error("Couldn't reach the Enso API: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// The API answered with a non-success status.
+    #[error("Enso API returned status {status}: {}", describe(.error, .body))]
+    Http {
+        status: u16,
+        retry_after: Option<Duration>,
+        error: Option<ApiError>,
+        body: String,
+    },
+
+    /// The API rejected the request because the quota of the API key was exceeded.
+    #[error("Enso API rate limit exceeded")]
+    RateLimited {
+        retry_after: Option<Duration>,
+        error: Option<ApiError>,
+    },
+
+    /// The API key is missing, invalid or not allowed to use the endpoint.
+    #[error("Enso API authentication failed with status {status}: {}", describe(.error, ""))]
+    Unauthorized {
+        status: u16,
+        error: Option<ApiError>,
+    },
+
+    /// The response body doesn't match the expected model.
+    #[error("Couldn't parse the Enso API response at `{path}`: {source}")]
+    Deserialize {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+        body: String,
+    },
+
+    /// A cassette couldn't be read or written, or has no interaction matching a
+    /// replayed request.
+    #[error("Cassette error: {0}")]
+    Cassette(String),
+
+    /// The client was configured with invalid settings.
+    #[error("Invalid Enso client configuration: {0}")]
+    Config(String),
+
+    /// A token list doesn't follow the Token Lists JSON schema.
+    #[error("Invalid token list: {0}")]
+    InvalidTokenList(String),
+}
+
+/// Error payload returned by the Enso API.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiError {
+    pub status_code: Option<u16>,
+    #[serde(default, deserialize_with = "string_or_list")]
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+impl From<reqwest::Error> for EnsoError {
+    fn from(error: reqwest::Error) -> Self {
+        EnsoError::Transport(Box::new(error))
+    }
+}
+
+impl EnsoError {
+    /// Builds the error matching a non-success response.
+    pub(crate) fn from_response(status: u16, retry_after: Option<Duration>, body: String) -> Self {
+        let error = serde_json::from_str::<ApiError>(&body).ok();
+        match status {
+            401 | 403 => EnsoError::Unauthorized { status, error },
+            429 => EnsoError::RateLimited { retry_after, error },
+            _ => EnsoError::Http {
+                status,
+                retry_after,
+                error,
+                body,
+            },
+        }
+    }
+}
+
+fn describe(error: &Option<ApiError>, body: &str) -> String {
+    error
+        .as_ref()
+        .and_then(|e| e.message.clone().or_else(|| e.error.clone()))
+        .unwrap_or_else(|| body.to_string())
+}
+
+fn string_or_list<'de, D>(des: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Message {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(
+        Option::<Message>::deserialize(des)?.map(|message| match message {
+            Message::One(message) => message,
+            Message::Many(messages) => messages.join(", "),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_response() {
+        let body =
+            r#"{"statusCode":400,"message":["chainId must be a number"],"error":"Bad Request"}"#;
+        let EnsoError::Http { status, error, .. } =
+            EnsoError::from_response(400, None, body.to_string())
+        else {
+            panic!("expected an HTTP error");
+        };
+        assert_eq!(status, 400);
+        assert_eq!(
+            error.and_then(|e| e.message).as_deref(),
+            Some("chainId must be a number")
+        );
+
+        assert!(matches!(
+            EnsoError::from_response(401, None, String::new()),
+            EnsoError::Unauthorized { status: 401, .. }
+        ));
+        assert!(matches!(
+            EnsoError::from_response(429, Some(Duration::from_secs(2)), String::new()),
+            EnsoError::RateLimited {
+                retry_after: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            EnsoError::from_response(503, Some(Duration::from_secs(2)), String::new()),
+            EnsoError::Http {
+                status: 503,
+                retry_after: Some(_),
+                ..
+            }
+        ));
+    }
+}
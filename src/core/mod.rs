@@ -1,7 +1,40 @@
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc, time::Instant};
+
+use reqwest::{
+    header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER},
+    Method,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::{field, Instrument, Span};
+
+pub use builder::EnsoBuilder;
+pub use cassette::{CassetteMode, CassetteTransport};
+pub use error::{ApiError, EnsoError, Result};
+pub use paginated::{Checkpoint, Meta, Page, PageResponse, Paged, Paginated};
+pub use query::IntoQuery;
+pub use rate_limit::{RateLimiter, RateLimiterStats};
+pub use retry::RetryPolicy;
+pub use transport::{
+    HttpRequest, HttpResponse, HttpTransport, InMemoryTransport, ReqwestTransport,
+};
+
+pub(crate) use endpoint::Endpoint;
+
+mod builder;
+mod cassette;
+mod endpoint;
+mod error;
+mod paginated;
+mod query;
+mod rate_limit;
+mod retry;
+mod transport;
 
 const API_ADDRESS: &str = "https://api.enso.finance";
 
+/// Version of the Enso API targeted by a client.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Version {
     V1,
 }
@@ -18,14 +51,26 @@ impl Display for Version {
     }
 }
 
+/// Client for the Enso API.
+///
+/// All clones share the same connection pool, so a single instance can be
+/// handed to many tasks cheaply.
+#[derive(Clone)]
 pub struct Enso {
+    inner: Arc<EnsoInner>,
+}
+
+pub(crate) struct EnsoInner {
     api_address: String,
-    pub(crate) api_key: String,
-    version: String,
+    api_key: String,
+    version: Version,
+    transport: Arc<dyn HttpTransport>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl Enso {
-    /// Creates a new `Enso` instance.
+    /// Creates a new `Enso` instance with the default settings.
     ///
     /// # Arguments
     ///
@@ -34,18 +79,177 @@ impl Enso {
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
+    /// # use enso::core::{Enso, Version};
     /// let enso = Enso::new("your_api_key", Version::V1);
     /// ```
     pub fn new<T: ToString>(api_key: T, version: Version) -> Enso {
-        Enso {
-            api_address: API_ADDRESS.to_string(),
-            api_key: api_key.to_string(),
-            version: version.to_string(),
-        }
+        Enso::builder()
+            .api_key(api_key)
+            .version(version)
+            .build()
+            .expect("Couldn't create the Enso client")
+    }
+
+    /// Creates an `EnsoBuilder` to configure the base URL, timeouts, user agent,
+    /// default headers and proxy of the client.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::core::Enso;
+    /// # fn example() -> enso::core::Result<()> {
+    /// let enso = Enso::builder()
+    ///     .api_key("your_api_key")
+    ///     .base_url("https://staging.example.com")
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> EnsoBuilder {
+        EnsoBuilder::default()
+    }
+
+    /// Returns the API version targeted by this client.
+    pub fn version(&self) -> Version {
+        self.inner.version
+    }
+
+    /// Returns the base URL of the API, e.g. `https://api.enso.finance`.
+    pub fn base_url(&self) -> &str {
+        &self.inner.api_address
     }
 
     pub(crate) fn get_api_url(&self) -> String {
-        format!("{}/api/{}", self.api_address, self.version)
+        format!("{}/api/{}", self.inner.api_address, self.inner.version)
     }
+
+    /// Returns the counters of the configured rate limiter, if any.
+    pub fn rate_limiter_stats(&self) -> Option<RateLimiterStats> {
+        self.inner
+            .rate_limiter
+            .as_ref()
+            .map(|limiter| limiter.stats())
+    }
+
+    /// Sends a `GET` request to `path` and parses the JSON response.
+    pub(crate) async fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(String, String)],
+    ) -> Result<T> {
+        let body = self.send(Method::GET, path, query, None).await?;
+        parse_json(&body)
+    }
+
+    /// Sends a request to `path`, relative to the versioned API URL, and returns
+    /// the raw body of a successful response.
+    ///
+    /// Every attempt waits for the rate limiter, if configured, and failed attempts
+    /// are retried according to the configured `RetryPolicy`. The request runs in an
+    /// `enso.request` span recording its endpoint, chain id, page, final status,
+    /// duration and retry count. Headers, and so the API key, are never recorded.
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(String, String)],
+        body: Option<&Value>,
+    ) -> Result<Vec<u8>> {
+        let param = |name: &str| {
+            query
+                .iter()
+                .find(|(f, _)| f == name)
+                .map(|(_, v)| v.as_str())
+        };
+        let span = tracing::info_span!(
+            "enso.request",
+            method = %method,
+            endpoint = path,
+            chain_id = param("chainId"),
+            page = param("page"),
+            status = field::Empty,
+            duration_ms = field::Empty,
+            retries = field::Empty,
+        );
+        let start = Instant::now();
+        let mut attempt = 1;
+        let result = async {
+            loop {
+                if let Some(limiter) = &self.inner.rate_limiter {
+                    limiter.acquire().await;
+                }
+                let error = match self.send_once(method.clone(), path, query, body).await {
+                    Ok(body) => return Ok(body),
+                    Err(error) => error,
+                };
+                let Some(delay) = self.inner.retry_policy.next_delay(&method, attempt, &error)
+                else {
+                    return Err(error);
+                };
+                tracing::warn!(attempt, ?delay, %error, "Retrying request");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        }
+        .instrument(span.clone())
+        .await;
+
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        span.record("retries", attempt - 1);
+        span.in_scope(|| match &result {
+            Ok(_) => tracing::debug!("Request succeeded"),
+            Err(error) => tracing::warn!(%error, "Request failed"),
+        });
+        result
+    }
+
+    async fn send_once(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(String, String)],
+        body: Option<&Value>,
+    ) -> Result<Vec<u8>> {
+        let mut headers = vec![(
+            AUTHORIZATION.to_string(),
+            format!("Bearer {}", self.inner.api_key),
+        )];
+        if body.is_some() {
+            headers.push((CONTENT_TYPE.to_string(), "application/json".to_string()));
+        }
+        let request = HttpRequest {
+            method,
+            url: format!("{}/{}", self.get_api_url(), path),
+            query: query.to_vec(),
+            headers,
+            body: body.map(|body| body.to_string().into_bytes()),
+        };
+        let response = self.inner.transport.send(request).await?;
+
+        let status = response.status;
+        Span::current().record("status", status);
+        let retry_after = response
+            .header(RETRY_AFTER.as_str())
+            .and_then(retry::parse_retry_after);
+        let body = response.body;
+        if !(200..300).contains(&status) {
+            return Err(EnsoError::from_response(
+                status,
+                retry_after,
+                String::from_utf8_lossy(&body).into_owned(),
+            ));
+        }
+        Ok(body)
+    }
+}
+
+/// Parses a JSON body, keeping the path of the field that failed and the raw body.
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    let des = &mut serde_json::Deserializer::from_slice(body);
+    serde_path_to_error::deserialize(des).map_err(|e| EnsoError::Deserialize {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+        body: String::from_utf8_lossy(body).into_owned(),
+    })
 }
@@ -0,0 +1,415 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    pin::Pin,
+    task::Poll,
+};
+
+use futures::{stream::FuturesUnordered, Future, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::Instrument;
+
+use super::{Endpoint, Enso, Result};
+
+/// Pagination details of a paged response.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meta {
+    /// The number of items matching the filter.
+    pub total: u32,
+    /// The number of the last page.
+    pub last_page: u32,
+    /// The number of the returned page, starting at 1.
+    pub current_page: u32,
+    /// The number of items per page.
+    pub per_page: u32,
+    /// The number of the previous page, if any.
+    pub prev: Option<u32>,
+    /// The number of the next page, if any.
+    pub next: Option<u32>,
+}
+
+/// Body of a paged endpoint, fetched page by page by a [`Paginated`] stream.
+pub trait PageResponse: DeserializeOwned + Send + Unpin + 'static {
+    /// The type of the items of a page.
+    type Item: Send + Unpin + 'static;
+
+    /// Returns the pagination details of the response.
+    fn meta(&self) -> &Meta;
+
+    /// Consumes the response, returning its items.
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// The usual body of a paged endpoint: a `meta` block and a `data` array.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Paged<T> {
+    pub meta: Meta,
+    pub data: Vec<T>,
+}
+
+impl<T: DeserializeOwned + Send + Unpin + 'static> PageResponse for Paged<T> {
+    type Item = T;
+
+    fn meta(&self) -> &Meta {
+        &self.meta
+    }
+
+    fn into_items(self) -> Vec<T> {
+        self.data
+    }
+}
+
+/// A page yielded by a [`Paginated`] stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page<T> {
+    /// The number of the page, starting at 1.
+    pub page: u32,
+    /// The number of pages, as reported by the API.
+    pub total_pages: u32,
+    /// The items of the page.
+    pub items: Vec<T>,
+}
+
+/// Progress of a [`Paginated`] stream, from which a new stream can resume.
+///
+/// Save it after processing each page, e.g. as JSON, and pass it back to the
+/// `resume_*` method of the endpoint to continue where a crashed job left off.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    /// The query parameters of the stream, without the page.
+    pub params: Vec<(String, String)>,
+    /// The first page that hasn't been yielded yet.
+    pub next_page: u32,
+    /// The number of pages, if already known.
+    pub total_pages: Option<u32>,
+    /// Pages after `next_page` that were already yielded by an unordered stream.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub yielded_pages: BTreeSet<u32>,
+}
+
+impl Checkpoint {
+    /// Creates a checkpoint starting at the first page of a stream with `params`.
+    pub fn new(params: Vec<(String, String)>) -> Checkpoint {
+        Checkpoint {
+            params: params.into_iter().filter(|(f, _)| f != "page").collect(),
+            next_page: 1,
+            total_pages: None,
+            yielded_pages: BTreeSet::new(),
+        }
+    }
+}
+
+type PageFuture<P> = Pin<Box<dyn Future<Output = (u32, Result<P>)> + Send>>;
+
+/// Stream of the pages of a paged endpoint.
+///
+/// The first page is fetched alone to learn the number of pages, then up to
+/// `concurrency` pages are fetched at once. Pages are yielded in order unless
+/// [`Paginated::unordered`] is set. Every request goes through the rate limiter and
+/// retry policy of the client, so a page that still fails is yielded as an error
+/// and ends the stream. Its checkpoint then resumes at the failed page.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::{core::{Checkpoint, Enso}, metadata::token_filter::TokenFilter};
+/// # use futures::StreamExt;
+/// # fn save(_: &Checkpoint) {}
+/// # async fn example(enso: Enso) -> enso::core::Result<()> {
+/// let mut pages = enso.tokens_pages(TokenFilter::new().chain_id(1)).concurrency(4);
+/// while let Some(page) = pages.next().await {
+///     let page = page?;
+///     println!("page {}/{}: {} tokens", page.page, page.total_pages, page.items.len());
+///     save(&pages.checkpoint());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Paginated<P: PageResponse> {
+    enso: Enso,
+    endpoint: Endpoint,
+    params: Vec<(String, String)>,
+    concurrency: usize,
+    ordered: bool,
+    first_page: u32,
+    next_page: u32,
+    next_to_yield: u32,
+    total_pages: Option<u32>,
+    yielded_pages: BTreeSet<u32>,
+    failed: bool,
+    in_flight: FuturesUnordered<PageFuture<P>>,
+    completed: BTreeMap<u32, Result<P>>,
+}
+
+impl<P: PageResponse> Paginated<P> {
+    /// Creates a stream of the pages of `endpoint` resuming from `checkpoint`.
+    pub(crate) fn new(enso: Enso, endpoint: Endpoint, checkpoint: Checkpoint) -> Paginated<P> {
+        let first_page = checkpoint.next_page.max(1);
+        Paginated {
+            enso,
+            endpoint,
+            params: checkpoint.params,
+            concurrency: 1,
+            ordered: true,
+            first_page,
+            next_page: first_page,
+            next_to_yield: first_page,
+            total_pages: checkpoint.total_pages,
+            yielded_pages: checkpoint.yielded_pages,
+            failed: false,
+            in_flight: FuturesUnordered::new(),
+            completed: BTreeMap::new(),
+        }
+    }
+
+    /// Sets how many pages may be fetched at once. Defaults to 1.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Yields pages as soon as they arrive instead of in order.
+    pub fn unordered(mut self) -> Self {
+        self.ordered = false;
+        self
+    }
+
+    /// Starts the stream at `page` instead of the first one.
+    ///
+    /// Must be called before the stream is polled.
+    pub fn start_page(mut self, page: u32) -> Self {
+        self.first_page = page.max(1);
+        self.next_page = self.first_page;
+        self.next_to_yield = self.first_page;
+        self
+    }
+
+    /// Returns the progress of the stream, covering every page yielded so far.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            params: self.params.clone(),
+            next_page: self.next_to_yield,
+            total_pages: self.total_pages,
+            yielded_pages: self.yielded_pages.clone(),
+        }
+    }
+
+    fn fetch(&self, page: u32) -> PageFuture<P> {
+        let enso = self.enso.clone();
+        let endpoint = self.endpoint;
+        let mut query = self.params.clone();
+        query.push(("page".to_string(), page.to_string()));
+        let span = tracing::info_span!("enso.page", %endpoint, page);
+        Box::pin(
+            async move {
+                let response = enso.get_json::<P>(endpoint.path(), &query).await;
+                (page, response)
+            }
+            .instrument(span),
+        )
+    }
+
+    /// Returns the next page to fetch, if any may be started now.
+    fn next_page_to_fetch(&mut self) -> Option<u32> {
+        // Until the first page tells how many there are, only one page is fetched.
+        let limit = match self.total_pages {
+            Some(_) => self.concurrency,
+            None => 1,
+        };
+        // Pages waiting for an earlier one to be yielded count against the limit.
+        if self.in_flight.len() + self.completed.len() >= limit {
+            return None;
+        }
+        while self.yielded_pages.contains(&self.next_page) {
+            self.next_page += 1;
+        }
+        let page = self.next_page;
+        let past_end = match self.total_pages {
+            Some(total) => page > total,
+            None => page > self.first_page,
+        };
+        if past_end {
+            return None;
+        }
+        self.next_page += 1;
+        Some(page)
+    }
+
+    /// Records that `page` was yielded and builds the item for it.
+    fn yield_page(&mut self, page: u32, response: P) -> Page<P::Item> {
+        if page == self.next_to_yield {
+            self.next_to_yield += 1;
+            while self.yielded_pages.remove(&self.next_to_yield) {
+                self.next_to_yield += 1;
+            }
+        } else {
+            self.yielded_pages.insert(page);
+        }
+        Page {
+            page,
+            total_pages: response.meta().last_page,
+            items: response.into_items(),
+        }
+    }
+
+    /// Ends the stream after a failed page, dropping the pages fetched after it.
+    fn fail(&mut self) {
+        self.failed = true;
+        self.in_flight.clear();
+        self.completed.clear();
+    }
+
+    fn is_done(&self) -> bool {
+        self.in_flight.is_empty()
+            && self.completed.is_empty()
+            && self
+                .total_pages
+                .is_some_and(|total| self.next_page > total || self.next_to_yield > total)
+    }
+}
+
+impl<P: PageResponse> Stream for Paginated<P> {
+    type Item = Result<Page<P::Item>>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.failed {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            while let Some(page) = this.next_page_to_fetch() {
+                let future = this.fetch(page);
+                this.in_flight.push(future);
+            }
+
+            let page = this.next_to_yield;
+            if let Some(result) = this.completed.remove(&page) {
+                return Poll::Ready(Some(match result {
+                    Ok(response) => Ok(this.yield_page(page, response)),
+                    Err(e) => {
+                        this.fail();
+                        Err(e)
+                    }
+                }));
+            }
+
+            if this.is_done() {
+                return Poll::Ready(None);
+            }
+
+            let Some((page, result)) = futures::ready!(this.in_flight.poll_next_unpin(cx)) else {
+                // Nothing in flight and nothing left to fetch.
+                return Poll::Ready(None);
+            };
+            if let Ok(response) = &result {
+                this.total_pages = Some(response.meta().last_page);
+            }
+            if this.ordered {
+                this.completed.insert(page, result);
+                continue;
+            }
+            return Poll::Ready(Some(match result {
+                Ok(response) => Ok(this.yield_page(page, response)),
+                Err(e) => {
+                    this.fail();
+                    Err(e)
+                }
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use reqwest::Method;
+    use serde_json::json;
+
+    use crate::{
+        core::{HttpResponse, InMemoryTransport},
+        testing::page_meta,
+    };
+
+    use super::*;
+
+    /// A page type with its own layout, as future paged endpoints may have.
+    #[derive(Deserialize)]
+    struct Balances {
+        meta: Meta,
+        balances: Vec<u64>,
+    }
+
+    impl PageResponse for Balances {
+        type Item = u64;
+
+        fn meta(&self) -> &Meta {
+            &self.meta
+        }
+
+        fn into_items(self) -> Vec<u64> {
+            self.balances
+        }
+    }
+
+    fn page(page: usize, balances: &[u64]) -> HttpResponse {
+        HttpResponse::json(
+            200,
+            &json!({ "meta": page_meta(page, 3, 2), "balances": balances }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_custom_page_response() {
+        let transport = Arc::new(
+            InMemoryTransport::new()
+                .on(Method::GET, "/api/v1/tokens", page(1, &[1, 2]))
+                .on_query(
+                    Method::GET,
+                    "/api/v1/tokens",
+                    &[("page", "2")],
+                    page(2, &[3]),
+                ),
+        );
+        let enso = Enso::builder()
+            .api_key("key")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let pages = Paginated::<Balances>::new(
+            enso,
+            Endpoint::Tokens,
+            Checkpoint::new(vec![("page".to_string(), "7".to_string())]),
+        )
+        .concurrency(2)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+        assert_eq!(
+            pages,
+            vec![
+                Page {
+                    page: 1,
+                    total_pages: 2,
+                    items: vec![1, 2],
+                },
+                Page {
+                    page: 2,
+                    total_pages: 2,
+                    items: vec![3],
+                },
+            ]
+        );
+        assert_eq!(transport.requests()[0].query_param("page"), Some("1"));
+    }
+}
@@ -0,0 +1,35 @@
+/// Conversion into the query parameters of a request.
+///
+/// Implemented for raw key-value pairs, so methods accepting `impl IntoQuery`
+/// keep working with `&[("chainId", "1")]`, and for typed filters such as
+/// `TokenFilter`.
+pub trait IntoQuery {
+    /// Returns the query parameters as owned key-value pairs.
+    fn into_query(self) -> Vec<(String, String)>;
+}
+
+impl IntoQuery for &[(&str, &str)] {
+    fn into_query(self) -> Vec<(String, String)> {
+        self.iter()
+            .map(|(f, v)| (f.to_string(), v.to_string()))
+            .collect()
+    }
+}
+
+impl<const N: usize> IntoQuery for &[(&str, &str); N] {
+    fn into_query(self) -> Vec<(String, String)> {
+        self.as_slice().into_query()
+    }
+}
+
+impl IntoQuery for &Vec<(&str, &str)> {
+    fn into_query(self) -> Vec<(String, String)> {
+        self.as_slice().into_query()
+    }
+}
+
+impl IntoQuery for Vec<(String, String)> {
+    fn into_query(self) -> Vec<(String, String)> {
+        self
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use super::{EnsoError, Result};
+
+/// Token-bucket rate limiter awaited before every request sent to the API.
+///
+/// A limiter is shared by every clone of the `Enso` it is configured on. Wrap it
+/// in an `Arc` and pass it to `EnsoBuilder::rate_limiter` to share a single quota
+/// between several clients.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::core::Enso;
+/// # fn example() -> enso::core::Result<()> {
+/// let enso = Enso::builder()
+///     .api_key("your_api_key")
+///     .rate_limit(10.0, 20)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    requests: AtomicU64,
+    throttled_requests: AtomicU64,
+    throttled_nanos: AtomicU64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Counters describing how much a `RateLimiter` delayed requests.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RateLimiterStats {
+    /// Requests that went through the limiter.
+    pub requests: u64,
+    /// Requests that had to wait for a token.
+    pub throttled_requests: u64,
+    /// Total time spent waiting for tokens.
+    pub throttled_time: Duration,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `requests_per_second` on average, with bursts of up
+    /// to `burst` requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the limiter, or an `EnsoError::Config` if the rate isn't
+    /// positive or the burst is zero.
+    pub fn new(requests_per_second: f64, burst: u32) -> Result<RateLimiter> {
+        if !(requests_per_second.is_finite() && requests_per_second > 0.0) {
+            return Err(EnsoError::Config(format!(
+                "Invalid rate limit: {} requests per second",
+                requests_per_second
+            )));
+        }
+        if burst == 0 {
+            return Err(EnsoError::Config(
+                "Rate limit burst must be at least 1".to_string(),
+            ));
+        }
+        Ok(RateLimiter {
+            requests_per_second,
+            burst: burst as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                updated_at: Instant::now(),
+            }),
+            requests: AtomicU64::new(0),
+            throttled_requests: AtomicU64::new(0),
+            throttled_nanos: AtomicU64::new(0),
+        })
+    }
+
+    /// Waits until a request can be sent.
+    pub async fn acquire(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let wait = self.reserve();
+        if wait.is_zero() {
+            return;
+        }
+        self.throttled_requests.fetch_add(1, Ordering::Relaxed);
+        self.throttled_nanos
+            .fetch_add(wait.as_nanos() as u64, Ordering::Relaxed);
+        tokio::time::sleep(wait).await;
+    }
+
+    /// Returns the counters collected so far.
+    pub fn stats(&self) -> RateLimiterStats {
+        RateLimiterStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            throttled_requests: self.throttled_requests.load(Ordering::Relaxed),
+            throttled_time: Duration::from_nanos(self.throttled_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Takes a token from the bucket and returns how long the caller must wait for it.
+    ///
+    /// The bucket may go negative, which queues callers in the order they arrived.
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+        bucket.updated_at = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.requests_per_second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_invalid_limits() {
+        assert!(RateLimiter::new(0.0, 1).is_err());
+        assert!(RateLimiter::new(f64::NAN, 1).is_err());
+        assert!(RateLimiter::new(1.0, 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_acquire() {
+        let limiter = RateLimiter::new(20.0, 2).unwrap();
+        let start = Instant::now();
+
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+
+        let stats = limiter.stats();
+        assert_eq!(stats.requests, 4);
+        assert_eq!(stats.throttled_requests, 2);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
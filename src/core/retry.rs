@@ -0,0 +1,233 @@
+use std::time::{Duration, SystemTime};
+
+use reqwest::Method;
+
+use super::EnsoError;
+
+/// Controls how failed requests are retried.
+///
+/// Transport failures, rate limiting (`429`), timeouts (`408`) and server errors
+/// (`5xx`) are retried with an exponential backoff. When the API sends a
+/// `Retry-After` header its value is used instead of the computed delay, and the
+/// request isn't retried if it's longer than the maximum delay.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use enso::core::{Enso, RetryPolicy};
+/// # fn example() -> enso::core::Result<()> {
+/// let policy = RetryPolicy::default()
+///     .max_attempts(5)
+///     .base_delay(Duration::from_millis(500));
+/// let enso = Enso::builder().api_key("your_api_key").retry_policy(policy).build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        RetryPolicy::default().max_attempts(1)
+    }
+
+    /// Sets the total number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry. Each following retry doubles it.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Sets the upper bound for the delay between attempts. A request asking to
+    /// retry after a longer delay fails instead.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Enables or disables randomizing the delay between attempts.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Allows retrying non-idempotent requests such as `send_bundle`.
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Returns how long to wait before the next attempt, or `None` if the request
+    /// shouldn't be retried.
+    ///
+    /// `attempt` is the number of the attempt that just failed, starting at 1.
+    pub(crate) fn next_delay(
+        &self,
+        method: &Method,
+        attempt: u32,
+        error: &EnsoError,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        if !method.is_idempotent() && !self.retry_non_idempotent {
+            return None;
+        }
+        let retry_after = match error {
+            EnsoError::RateLimited { retry_after, .. } => retry_after,
+            EnsoError::Http {
+                status,
+                retry_after,
+                ..
+            } if *status == 408 || *status >= 500 => retry_after,
+            EnsoError::Transport(_) => &None,
+            _ => return None,
+        };
+        match retry_after {
+            Some(retry_after) => (*retry_after <= self.max_delay).then_some(*retry_after),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        if self.jitter {
+            // Picks a delay between half and the full backoff.
+            delay / 2 + delay.mul_f64(fastrand::f64() / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    httpdate::parse_http_date(value)
+        .ok()
+        .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn server_error() -> EnsoError {
+        EnsoError::Http {
+            status: 503,
+            retry_after: None,
+            error: None,
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default()
+            .max_attempts(4)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300))
+            .jitter(false);
+
+        let delays = (1..=4)
+            .map(|attempt| policy.next_delay(&Method::GET, attempt, &server_error()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(300)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_after_and_idempotency() {
+        let policy = RetryPolicy::default();
+        let rate_limited = EnsoError::RateLimited {
+            retry_after: Some(Duration::from_secs(7)),
+            error: None,
+        };
+
+        assert_eq!(
+            policy.next_delay(&Method::GET, 1, &rate_limited),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(policy.next_delay(&Method::POST, 1, &rate_limited), None);
+        assert_eq!(
+            policy.clone().max_delay(Duration::from_secs(5)).next_delay(
+                &Method::GET,
+                1,
+                &rate_limited
+            ),
+            None
+        );
+        let overloaded = EnsoError::Http {
+            status: 503,
+            retry_after: Some(Duration::from_secs(7)),
+            error: None,
+            body: String::new(),
+        };
+        assert_eq!(
+            policy.next_delay(&Method::GET, 1, &overloaded),
+            Some(Duration::from_secs(7))
+        );
+        assert_eq!(
+            policy.clone().max_delay(Duration::from_secs(5)).next_delay(
+                &Method::GET,
+                1,
+                &overloaded
+            ),
+            None
+        );
+        assert!(policy
+            .retry_non_idempotent(true)
+            .next_delay(&Method::POST, 1, &server_error())
+            .is_some());
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after(" 3 "), Some(Duration::from_secs(3)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
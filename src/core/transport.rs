@@ -0,0 +1,420 @@
+use std::{collections::VecDeque, fmt, sync::Mutex};
+
+use futures::future::BoxFuture;
+use reqwest::{header::AUTHORIZATION, Client, Method, Url};
+use serde_json::Value;
+
+use super::Result;
+
+/// A request sent by `Enso` through an [`HttpTransport`].
+///
+/// The `Debug` output redacts the `Authorization` header, so requests can be
+/// logged without leaking the API key.
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    /// Returns the path of the request URL, e.g. `/api/v1/tokens`.
+    pub fn path(&self) -> String {
+        Url::parse(&self.url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| self.url.clone())
+    }
+
+    /// Returns the value of the query parameter `name`, if present.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(f, _)| f == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                if name.eq_ignore_ascii_case(AUTHORIZATION.as_str()) {
+                    (name.as_str(), "<redacted>")
+                } else {
+                    (name.as_str(), value.as_str())
+                }
+            })
+            .collect::<Vec<_>>();
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("query", &self.query)
+            .field("headers", &headers)
+            .field("body", &self.body.as_deref().map(String::from_utf8_lossy))
+            .finish()
+    }
+}
+
+/// A response received by an [`HttpTransport`].
+#[derive(Clone, Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Creates a response with the given status and raw body.
+    pub fn new<B: Into<Vec<u8>>>(status: u16, body: B) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Creates a response with the given status and JSON body.
+    pub fn json(status: u16, body: &Value) -> HttpResponse {
+        HttpResponse::new(status, body.to_string()).with_header("content-type", "application/json")
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header<K: ToString, V: ToString>(mut self, name: K, value: V) -> HttpResponse {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the value of the header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(f, _)| f.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Sends the HTTP requests of an `Enso` client.
+///
+/// The default implementation is [`ReqwestTransport`]. Implement this trait to plug
+/// in a custom HTTP stack, or use [`InMemoryTransport`] to exercise code depending
+/// on `Enso` without the network.
+pub trait HttpTransport: Send + Sync {
+    /// Sends `request` and returns the response, whatever its status.
+    ///
+    /// Errors are reserved for failures to send the request or read the response.
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse>>;
+}
+
+/// [`HttpTransport`] backed by a `reqwest::Client`.
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    /// Creates a transport sending requests through `client`.
+    pub fn new(client: Client) -> ReqwestTransport {
+        ReqwestTransport { client }
+    }
+}
+
+impl HttpTransport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse>> {
+        Box::pin(async move {
+            let mut builder = self
+                .client
+                .request(request.method, &request.url)
+                .query(&request.query);
+            for (name, value) in &request.headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let response = builder.send().await?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    value
+                        .to_str()
+                        .ok()
+                        .map(|value| (name.to_string(), value.to_string()))
+                })
+                .collect();
+            let body = response.bytes().await?.to_vec();
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+struct Route {
+    method: Method,
+    path: String,
+    query: Vec<(String, String)>,
+    responses: VecDeque<HttpResponse>,
+}
+
+/// [`HttpTransport`] answering requests with canned responses.
+///
+/// Responses are matched by method, URL path and, optionally, a set of query
+/// parameters. When several responses are registered for the same route they are
+/// returned in order, the last one being repeated. Unmatched requests get a `404`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use enso::core::{Enso, HttpResponse, InMemoryTransport};
+/// # use reqwest::Method;
+/// # use serde_json::json;
+/// # async fn example() -> enso::core::Result<()> {
+/// let transport = Arc::new(InMemoryTransport::new().on(
+///     Method::GET,
+///     "/api/v1/networks",
+///     HttpResponse::json(200, &json!([{ "id": 1, "name": "Ethereum" }])),
+/// ));
+/// let enso = Enso::builder()
+///     .api_key("your_api_key")
+///     .transport(transport.clone())
+///     .build()?;
+/// let networks = enso.get_networks().await?;
+/// assert_eq!(transport.requests().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct InMemoryTransport {
+    routes: Mutex<Vec<Route>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl InMemoryTransport {
+    /// Creates a transport without any canned response.
+    pub fn new() -> InMemoryTransport {
+        InMemoryTransport::default()
+    }
+
+    /// Registers a response for requests to `path`.
+    pub fn on(self, method: Method, path: &str, response: HttpResponse) -> InMemoryTransport {
+        self.on_query(method, path, &[], response)
+    }
+
+    /// Registers a response for requests to `path` carrying all the given query
+    /// parameters.
+    pub fn on_query(
+        self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        response: HttpResponse,
+    ) -> InMemoryTransport {
+        {
+            let query = query
+                .iter()
+                .map(|(f, v)| (f.to_string(), v.to_string()))
+                .collect::<Vec<_>>();
+            let mut routes = self.routes.lock().unwrap();
+            match routes
+                .iter_mut()
+                .find(|r| r.method == method && r.path == path && r.query == query)
+            {
+                Some(route) => route.responses.push_back(response),
+                None => routes.push(Route {
+                    method,
+                    path: path.to_string(),
+                    query,
+                    responses: VecDeque::from([response]),
+                }),
+            }
+        }
+        self
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn respond(&self, request: &HttpRequest) -> HttpResponse {
+        let path = request.path();
+        let mut routes = self.routes.lock().unwrap();
+        // Routes with more query parameters are more specific.
+        let route = routes
+            .iter_mut()
+            .filter(|r| r.method == request.method && r.path == path)
+            .filter(|r| {
+                r.query
+                    .iter()
+                    .all(|(f, v)| request.query_param(f) == Some(v.as_str()))
+            })
+            .max_by_key(|r| r.query.len());
+        match route {
+            Some(route) if route.responses.len() > 1 => route.responses.pop_front().unwrap(),
+            Some(route) => route.responses[0].clone(),
+            None => HttpResponse::json(
+                404,
+                &serde_json::json!({
+                    "statusCode": 404,
+                    "message": format!("No canned response for {} {}", request.method, path),
+                }),
+            ),
+        }
+    }
+}
+
+impl HttpTransport for InMemoryTransport {
+    fn send(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse>> {
+        let response = self.respond(&request);
+        self.requests.lock().unwrap().push(request);
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use crate::{
+        bundle::core::Bundle,
+        core::{Enso, EnsoError, RetryPolicy},
+        testing::page_response,
+    };
+
+    use super::*;
+
+    fn tokens_page(page: usize, addresses: &[&str]) -> HttpResponse {
+        let data = addresses
+            .iter()
+            .map(|address| {
+                json!({
+                    "chainId": 10,
+                    "address": address,
+                    "type": "base",
+                    "protocolSlug": "enso",
+                    "underlyingTokens": [],
+                    "primaryAddress": address,
+                })
+            })
+            .collect();
+        page_response(page, 3, 2, data)
+    }
+
+    fn create_enso(transport: Arc<InMemoryTransport>) -> Enso {
+        Enso::builder()
+            .api_key("key")
+            .transport(transport)
+            .retry_policy(RetryPolicy::default().base_delay(std::time::Duration::ZERO))
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_metadata_offline() {
+        let transport = Arc::new(
+            InMemoryTransport::new()
+                .on(
+                    Method::GET,
+                    "/api/v1/networks",
+                    HttpResponse::json(200, &json!([{ "id": 10, "name": "Optimism" }])),
+                )
+                .on(
+                    Method::GET,
+                    "/api/v1/protocols",
+                    HttpResponse::json(200, &json!([{ "slug": "aave-v3", "url": "" }])),
+                )
+                .on(
+                    Method::GET,
+                    "/api/v1/actions",
+                    HttpResponse::json(
+                        200,
+                        &json!([{ "action": "route", "inputs": { "amountIn": "" } }]),
+                    ),
+                ),
+        );
+        let enso = create_enso(transport.clone());
+
+        assert_eq!(enso.get_networks().await.unwrap()[0].name, "Optimism");
+        assert_eq!(enso.get_protocols().await.unwrap()[0].slug, "aave-v3");
+        assert_eq!(enso.get_actions().await.unwrap()[0].action, "route");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0]
+            .headers
+            .contains(&("authorization".to_string(), "Bearer key".to_string())));
+        let debug = format!("{:?}", requests[0]);
+        assert!(debug.contains("<redacted>") && !debug.contains("Bearer key"));
+    }
+
+    #[tokio::test]
+    async fn test_tokens_offline() {
+        let transport = Arc::new(
+            InMemoryTransport::new()
+                .on(
+                    Method::GET,
+                    "/api/v1/tokens",
+                    tokens_page(1, &["0x1", "0x2"]),
+                )
+                .on_query(
+                    Method::GET,
+                    "/api/v1/tokens",
+                    &[("page", "2")],
+                    tokens_page(2, &["0x3"]),
+                ),
+        );
+        let enso = create_enso(transport.clone());
+
+        let (_, tokens) = enso.get_tokens(&[("chainId", "10")]).await.unwrap();
+        assert_eq!(tokens, vec!["0x1", "0x2"]);
+
+        let tokens = enso
+            .tokens_stream(&[("chainId", "10")])
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(tokens.concat(), vec!["0x1", "0x2", "0x3"]);
+        assert_eq!(transport.requests()[2].query_param("page"), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn test_retries_offline() {
+        let transport = Arc::new(
+            InMemoryTransport::new()
+                .on(Method::GET, "/api/v1/networks", HttpResponse::new(503, ""))
+                .on(
+                    Method::GET,
+                    "/api/v1/networks",
+                    HttpResponse::json(200, &json!([])),
+                )
+                .on(
+                    Method::POST,
+                    "/api/v1/shortcuts/bundle",
+                    HttpResponse::new(503, ""),
+                ),
+        );
+        let enso = create_enso(transport.clone());
+
+        assert!(enso.get_networks().await.unwrap().is_empty());
+        assert!(matches!(
+            enso.send_bundle(Bundle::new(1), "0x").await,
+            Err(EnsoError::Http { status: 503, .. })
+        ));
+        assert_eq!(transport.requests().len(), 3);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod bundle;
 pub mod core;
 pub mod metadata;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod wallet;
@@ -1,11 +1,20 @@
+use std::time::Duration;
+
 use anyhow::Result;
 
 use enso::{
     bundle::{actions::Action, core::Bundle},
     core::{Enso, Version},
-    metadata::{networks::Network, protocols::Protocol},
+    metadata::{
+        cache::MetadataCache,
+        chains::Chain,
+        networks::Network,
+        prices::{Price, PriceCache},
+        protocols::Protocol,
+        token_filter::TokenFilter,
+        tokens::Token,
+    },
 };
-use futures::StreamExt;
 use tokio::{
     spawn,
     sync::mpsc::{self, Receiver, Sender},
@@ -17,6 +26,9 @@ mod ui;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    #[cfg(feature = "example-logs")]
+    init_tracing()?;
+
     let (ui_to_business_sender, ui_to_business_receiver) = mpsc::channel::<UIRequest>(32);
     let (business_to_ui_sender, business_to_ui_receiver) = mpsc::channel::<BusinessResponse>(32);
 
@@ -33,22 +45,45 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Writes logs to `enso.log`, since the terminal is taken by the UI.
+///
+/// The verbosity is read from `RUST_LOG` and defaults to `info`. Only enabled with
+/// the `example-logs` feature, so the library doesn't depend on a subscriber.
+#[cfg(feature = "example-logs")]
+fn init_tracing() -> Result<()> {
+    use tracing_subscriber::EnvFilter;
+
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open("enso.log")?;
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false)
+        .init();
+    Ok(())
+}
+
 #[derive(Debug)]
 pub enum UIRequest {
     GetNetworks,
     SetNetwork(u32),
     GetTokens,
     GetProtocols,
-    GetActions,
+    GetProtocolActions(String),
     SendBundle(DataTransaction),
     Quit,
 }
 
 pub enum BusinessResponse {
-    Tokens(Vec<String>),
+    Tokens(Vec<Token>),
     Protocols(Vec<Protocol>),
-    Actions(Vec<Action>),
+    ProtocolActions(String, Vec<Action>),
     Networks(Vec<Network>),
+    Prices(u32, Vec<Price>),
 }
 
 async fn business(
@@ -57,56 +92,97 @@ async fn business(
 ) {
     let config = config::Config::default();
     let enso = Enso::new(config.api_key, Version::V1);
-    let mut chain_id: Option<u32> = None;
+    let cache = MetadataCache::new(enso.clone(), config.cache_dir);
+    let prices = PriceCache::new(enso.clone(), Duration::from_secs(60));
+    let mut chain = Chain::default();
 
     loop {
         match ui_to_business_receiver.recv().await {
             Some(UIRequest::GetTokens) => {
-                let mut tokens = Vec::new();
-                let mut tokens_streams =
-                    enso.tokens_stream(&[("chainId", &format!("{}", chain_id.unwrap_or(1)))]);
-                while let Some(tokens_received) = tokens_streams.next().await {
-                    match tokens_received {
-                        Ok(tokens_received) => tokens.extend(tokens_received),
-                        Err(e) => println!("{:?}", e),
-                    }
-                }
+                let filter = TokenFilter::new()
+                    .chain_id(chain.id())
+                    .include_metadata(true);
+                let tokens = cache.tokens(&filter).await.unwrap_or_else(|e| {
+                    tracing::error!(error = %e, "Couldn't fetch tokens");
+                    Vec::new()
+                });
+                tracing::debug!(stats = ?cache.stats(), "Metadata cache");
 
+                let addresses = tokens
+                    .iter()
+                    .map(|token| token.address.clone())
+                    .collect::<Vec<_>>();
                 business_to_ui_sender
                     .send(BusinessResponse::Tokens(tokens))
                     .await
                     .unwrap();
+
+                // Prices come later, so the tokens can be browsed meanwhile.
+                let prices = prices.clone();
+                let sender = business_to_ui_sender.clone();
+                let chain_id = chain.id();
+                spawn(async move {
+                    match prices.get_many(chain_id, &addresses).await {
+                        Ok(prices) => {
+                            _ = sender
+                                .send(BusinessResponse::Prices(chain_id, prices))
+                                .await
+                        }
+                        Err(e) => tracing::error!(error = %e, "Couldn't fetch prices"),
+                    }
+                });
             }
             Some(UIRequest::GetProtocols) => {
-                let protocols = enso.get_protocols().await.unwrap();
+                let protocols = cache
+                    .protocols_by_chain(chain.id())
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::error!(error = %e, "Couldn't fetch protocols");
+                        Vec::new()
+                    });
                 business_to_ui_sender
                     .send(BusinessResponse::Protocols(protocols))
                     .await
                     .unwrap();
             }
-            Some(UIRequest::GetActions) => {
-                let actions = enso.get_actions().await.unwrap();
-                business_to_ui_sender
-                    .send(BusinessResponse::Actions(actions))
-                    .await
-                    .unwrap();
+            Some(UIRequest::GetProtocolActions(slug)) => {
+                // Nothing is sent on failure, so the UI asks again when the
+                // protocol is selected again.
+                match cache.protocol_actions(&slug).await {
+                    Ok(actions) => business_to_ui_sender
+                        .send(BusinessResponse::ProtocolActions(slug, actions))
+                        .await
+                        .unwrap(),
+                    Err(e) => tracing::error!(error = %e, %slug, "Couldn't fetch protocol actions"),
+                }
             }
             Some(UIRequest::SendBundle(data)) => {
-                let mut bundle = Bundle::new(1);
+                let mut bundle = Bundle::new(chain.id());
                 data.into_iter().for_each(|(action, protocol, args)| {
                     bundle.add_action(protocol, action, args);
                 });
-                let _ = enso.send_bundle(bundle, "0x").await;
+                match enso.send_bundle(bundle, "0x").await {
+                    Ok(response) => tracing::info!(
+                        %chain,
+                        to = chain.address_url(&response.tx.to),
+                        gas = response.gas,
+                        "Bundle built"
+                    ),
+                    Err(e) => tracing::error!(error = %e, "Couldn't send bundle"),
+                }
             }
             Some(UIRequest::GetNetworks) => {
-                let networks = enso.get_networks().await.unwrap();
+                let networks = cache.networks().await.unwrap_or_else(|e| {
+                    tracing::error!(error = %e, "Couldn't fetch networks");
+                    Vec::new()
+                });
                 business_to_ui_sender
                     .send(BusinessResponse::Networks(networks))
                     .await
                     .unwrap();
             }
             Some(UIRequest::SetNetwork(id)) => {
-                chain_id = Some(id);
+                chain = Chain::from(id);
             }
             Some(UIRequest::Quit) => break,
             None => break,
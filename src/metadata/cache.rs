@@ -0,0 +1,537 @@
+//! On-disk cache of the metadata endpoints, so networks, protocols, actions and
+//! tokens aren't downloaded again on every launch.
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::TryStreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    bundle::actions::Action,
+    core::{Enso, IntoQuery, Result},
+};
+
+use super::{networks::Network, protocols::Protocol, tokens::Token};
+
+/// Kind of metadata stored by a [`MetadataCache`], each one with its own TTL.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    Networks,
+    Protocols,
+    Actions,
+    Tokens,
+}
+
+impl CacheKind {
+    fn default_ttl(self) -> Duration {
+        match self {
+            CacheKind::Networks | CacheKind::Actions => Duration::from_secs(24 * 60 * 60),
+            CacheKind::Protocols => Duration::from_secs(6 * 60 * 60),
+            CacheKind::Tokens => Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl Display for CacheKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CacheKind::Networks => "networks",
+                CacheKind::Protocols => "protocols",
+                CacheKind::Actions => "actions",
+                CacheKind::Tokens => "tokens",
+            }
+        )
+    }
+}
+
+/// Counters of a [`MetadataCache`], see [`MetadataCache::stats`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Requests answered with a fresh entry.
+    pub hits: u64,
+    /// Requests answered with an expired entry.
+    pub stale_hits: u64,
+    /// Requests that had to wait for the API.
+    pub misses: u64,
+    /// Expired entries refreshed in the background.
+    pub revalidations: u64,
+    /// Failed fetches, in the foreground or in the background.
+    pub errors: u64,
+}
+
+/// Cache of the metadata endpoints stored as JSON files under a directory.
+///
+/// Entries are kept per API host and version, and per filter for the tokens, so
+/// clients of different hosts can share a directory. An entry
+/// older than the TTL of its kind is expired: it is returned right away while a
+/// background task fetches the new data, unless stale-while-revalidate is turned
+/// off. An expired entry is also returned when the API can't be reached. Failing
+/// to read or write the directory never fails a request, the API is used instead.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use enso::metadata::{cache::{CacheKind, MetadataCache}, token_filter::TokenFilter};
+/// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+/// let cache = MetadataCache::new(enso.clone(), ".enso-cache")
+///     .ttl(CacheKind::Tokens, Duration::from_secs(10 * 60));
+/// let tokens = cache.tokens(TokenFilter::new().chain_id(1).include_metadata(true)).await?;
+/// println!("{:?}", cache.stats());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct MetadataCache {
+    enso: Enso,
+    dir: PathBuf,
+    ttls: [Duration; 4],
+    stale_while_revalidate: bool,
+    state: Arc<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    stats: Mutex<CacheStats>,
+    revalidating: Mutex<HashSet<PathBuf>>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry<T> {
+    stored_at: u64,
+    data: T,
+}
+
+impl MetadataCache {
+    /// Creates a cache storing the responses of `enso` under `dir`, created when
+    /// first written.
+    pub fn new<P: Into<PathBuf>>(enso: Enso, dir: P) -> MetadataCache {
+        let ttls = [
+            CacheKind::Networks,
+            CacheKind::Protocols,
+            CacheKind::Actions,
+            CacheKind::Tokens,
+        ]
+        .map(CacheKind::default_ttl);
+        MetadataCache {
+            enso,
+            dir: dir.into(),
+            ttls,
+            stale_while_revalidate: true,
+            state: Arc::default(),
+        }
+    }
+
+    /// Sets how long the entries of `kind` are fresh. Defaults to a day for the
+    /// networks and actions, 6 hours for the protocols and an hour for the tokens.
+    pub fn ttl(mut self, kind: CacheKind, ttl: Duration) -> Self {
+        self.ttls[kind as usize] = ttl;
+        self
+    }
+
+    /// Sets whether expired entries are returned while being refreshed in the
+    /// background (the default), or refreshed before returning.
+    pub fn stale_while_revalidate(mut self, enabled: bool) -> Self {
+        self.stale_while_revalidate = enabled;
+        self
+    }
+
+    /// Returns the directory of the cache.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the counters of the cache, shared by its clones.
+    pub fn stats(&self) -> CacheStats {
+        *self.state.stats.lock().unwrap()
+    }
+
+    /// Returns the networks, see [`Enso::get_networks`].
+    pub async fn networks(&self) -> Result<Vec<Network>> {
+        self.cached(CacheKind::Networks, None, |enso| async move {
+            enso.get_networks().await
+        })
+        .await
+    }
+
+    /// Returns the protocols, see [`Enso::get_protocols`].
+    pub async fn protocols(&self) -> Result<Vec<Protocol>> {
+        self.cached(CacheKind::Protocols, None, |enso| async move {
+            enso.get_protocols().await
+        })
+        .await
+    }
+
+    /// Returns the protocols of the chain `chain_id`, see
+    /// [`Enso::get_protocols_by_chain`].
+    pub async fn protocols_by_chain(&self, chain_id: u32) -> Result<Vec<Protocol>> {
+        let name = entry_name(&[("chainId".to_string(), chain_id.to_string())]);
+        self.cached(CacheKind::Protocols, Some(name), move |enso| async move {
+            enso.get_protocols_by_chain(chain_id).await
+        })
+        .await
+    }
+
+    /// Returns the actions, see [`Enso::get_actions`].
+    pub async fn actions(&self) -> Result<Vec<Action>> {
+        self.cached(CacheKind::Actions, None, |enso| async move {
+            enso.get_actions().await
+        })
+        .await
+    }
+
+    /// Returns the actions of the protocol `slug`, see [`Enso::get_protocol_actions`].
+    pub async fn protocol_actions(&self, slug: &str) -> Result<Vec<Action>> {
+        let name = entry_name(&[("protocol".to_string(), slug.to_string())]);
+        let slug = slug.to_string();
+        self.cached(CacheKind::Actions, Some(name), |enso| async move {
+            enso.get_protocol_actions(&slug).await
+        })
+        .await
+    }
+
+    /// Returns every token matching `params`, fetched page by page on a miss.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The filter of the tokens, e.g. a `TokenFilter`. The `page`
+    ///   parameter is ignored.
+    pub async fn tokens(&self, params: impl IntoQuery) -> Result<Vec<Token>> {
+        let mut query = params.into_query();
+        query.retain(|(name, _)| name != "page");
+        query.sort();
+        let name = entry_name(&query);
+        self.cached(CacheKind::Tokens, Some(name), |enso| async move {
+            let pages = enso
+                .tokens_pages(query)
+                .concurrency(4)
+                .try_collect::<Vec<_>>()
+                .await?;
+            Ok(pages.into_iter().flat_map(|page| page.items).collect())
+        })
+        .await
+    }
+
+    /// Removes the entries of `kind`.
+    pub async fn invalidate(&self, kind: CacheKind) {
+        let path = self.kind_path(kind);
+        // Filtered entries are stored in a directory named after the kind.
+        for removed in [
+            fs::remove_file(path.with_extension("json")).await,
+            fs::remove_dir_all(&path).await,
+        ] {
+            if let Err(e) = removed.or_else(not_found) {
+                tracing::debug!(error = %e, %kind, "Couldn't remove cache entries");
+            }
+        }
+    }
+
+    /// Removes every entry of the client's API host and version.
+    pub async fn clear(&self) {
+        if let Err(e) = fs::remove_dir_all(self.api_dir()).await {
+            tracing::debug!(error = %e, "Couldn't clear the cache");
+        }
+    }
+
+    /// Returns the directory of the entries of the client, e.g.
+    /// `api.enso.finance/v1` or `127.0.0.1%3A8080/v1`.
+    fn api_dir(&self) -> PathBuf {
+        let base_url = self.enso.base_url();
+        let host = base_url
+            .split_once("://")
+            .map_or(base_url, |(_, host)| host);
+        self.dir
+            .join(escape(host))
+            .join(self.enso.version().to_string())
+    }
+
+    fn kind_path(&self, kind: CacheKind) -> PathBuf {
+        self.api_dir().join(kind.to_string())
+    }
+
+    fn entry_path(&self, kind: CacheKind, name: Option<&str>) -> PathBuf {
+        match name {
+            Some(name) => self.kind_path(kind).join(format!("{}.json", name)),
+            None => self.kind_path(kind).with_extension("json"),
+        }
+    }
+
+    fn count(&self, update: impl FnOnce(&mut CacheStats)) {
+        update(&mut self.state.stats.lock().unwrap());
+    }
+
+    async fn cached<T, F, Fut>(&self, kind: CacheKind, name: Option<String>, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync + 'static,
+        F: FnOnce(Enso) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let path = self.entry_path(kind, name.as_deref());
+        let Some(entry) = read_entry::<T>(&path).await else {
+            self.count(|stats| stats.misses += 1);
+            return self.fetch(path, fetch).await;
+        };
+
+        let age = Duration::from_secs(now().saturating_sub(entry.stored_at));
+        if age < self.ttls[kind as usize] {
+            self.count(|stats| stats.hits += 1);
+            return Ok(entry.data);
+        }
+
+        if !self.stale_while_revalidate {
+            self.count(|stats| stats.misses += 1);
+            return match self.fetch(path, fetch).await {
+                Ok(data) => Ok(data),
+                Err(e) => {
+                    tracing::warn!(error = %e, %kind, "Using expired cache entry");
+                    Ok(entry.data)
+                }
+            };
+        }
+
+        self.count(|stats| stats.stale_hits += 1);
+        if self.state.revalidating.lock().unwrap().insert(path.clone()) {
+            let cache = self.clone();
+            tokio::spawn(async move {
+                if cache.fetch(path.clone(), fetch).await.is_ok() {
+                    cache.count(|stats| stats.revalidations += 1);
+                }
+                cache.state.revalidating.lock().unwrap().remove(&path);
+            });
+        }
+        Ok(entry.data)
+    }
+
+    async fn fetch<T, F, Fut>(&self, path: PathBuf, fetch: F) -> Result<T>
+    where
+        T: Serialize,
+        F: FnOnce(Enso) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        match fetch(self.enso.clone()).await {
+            Ok(data) => {
+                let entry = Entry {
+                    stored_at: now(),
+                    data,
+                };
+                if let Err(e) = write_entry(&path, &entry).await {
+                    tracing::warn!(error = %e, path = %path.display(), "Couldn't write cache entry");
+                }
+                Ok(entry.data)
+            }
+            Err(e) => {
+                self.count(|stats| stats.errors += 1);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn not_found(e: std::io::Error) -> std::io::Result<()> {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => Ok(()),
+        _ => Err(e),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Returns the file name of the entry filtered by `query`, e.g.
+/// `chainId=10_includeMetadata=true`.
+///
+/// Characters that could be ambiguous or invalid in a file name, including the
+/// `=` and `_` separators, are percent-encoded, so distinct queries never share
+/// an entry.
+fn entry_name(query: &[(String, String)]) -> String {
+    if query.is_empty() {
+        return "all".to_string();
+    }
+    query
+        .iter()
+        .map(|(name, value)| format!("{}={}", escape(name), escape(value)))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' => char::from(b).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+async fn read_entry<T: DeserializeOwned>(path: &Path) -> Option<Entry<T>> {
+    let bytes = fs::read(path).await.ok()?;
+    match serde_json::from_slice(&bytes) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            tracing::warn!(error = %e, path = %path.display(), "Ignoring invalid cache entry");
+            None
+        }
+    }
+}
+
+/// Writes to a temporary file first, so a reader never sees a partial entry.
+async fn write_entry<T: Serialize>(path: &Path, entry: &Entry<T>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    let temp = path.with_extension(format!("{}.tmp", fastrand::u32(..)));
+    fs::write(&temp, serde_json::to_vec(entry)?).await?;
+    fs::rename(&temp, path).await
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{metadata::token_filter::TokenFilter, testing::MockServer};
+
+    use super::*;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("enso-cache-{}-{}", name, std::process::id()));
+        _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_hits_and_misses() {
+        let server = MockServer::start();
+        let dir = cache_dir("hits");
+        let cache = MetadataCache::new(server.enso(), &dir);
+        let filter = TokenFilter::new().chain_id(10).include_metadata(true);
+
+        let networks = cache.networks().await.unwrap();
+        assert_eq!(cache.networks().await.unwrap().len(), networks.len());
+        let tokens = cache.tokens(&filter).await.unwrap();
+        assert_eq!(cache.tokens(filter.clone().page(3)).await.unwrap(), tokens);
+        assert!(cache.actions().await.is_ok());
+        let api_dir = dir.join(escape(server.url().trim_start_matches("http://")));
+        assert!(api_dir
+            .join("v1/tokens/chainId=10_includeMetadata=true.json")
+            .exists());
+
+        let requests = server.requests().len();
+        let reopened = MetadataCache::new(server.enso(), &dir);
+        assert_eq!(reopened.tokens(&filter).await.unwrap(), tokens);
+        assert_eq!(server.requests().len(), requests);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 3,
+                ..CacheStats::default()
+            }
+        );
+
+        cache.invalidate(CacheKind::Tokens).await;
+        cache.tokens(&filter).await.unwrap();
+        assert_eq!(cache.stats().misses, 4);
+        cache.clear().await;
+        assert!(!api_dir.join("v1").exists());
+    }
+
+    #[tokio::test]
+    async fn test_hosts_sharing_dir() {
+        let server = MockServer::start();
+        let other = MockServer::start();
+        let dir = cache_dir("hosts");
+
+        MetadataCache::new(server.enso(), &dir)
+            .networks()
+            .await
+            .unwrap();
+        let cache = MetadataCache::new(other.enso(), &dir);
+        cache.networks().await.unwrap();
+
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(other.requests().len(), 1);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate() {
+        let server = MockServer::start();
+        let dir = cache_dir("stale");
+        let cache =
+            MetadataCache::new(server.enso(), &dir).ttl(CacheKind::Protocols, Duration::ZERO);
+
+        cache.protocols().await.unwrap();
+        cache.protocols().await.unwrap();
+        for _ in 0..100 {
+            if cache.stats().revalidations == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                misses: 1,
+                stale_hits: 1,
+                revalidations: 1,
+                ..CacheStats::default()
+            }
+        );
+
+        // Without revalidation, an unreachable API falls back to the expired entry.
+        let url = server.url().to_string();
+        drop(server);
+        let offline = Enso::builder()
+            .api_key("any")
+            .base_url(url)
+            .retry_policy(crate::core::RetryPolicy::none())
+            .build()
+            .unwrap();
+        let cache = MetadataCache::new(offline, &dir)
+            .ttl(CacheKind::Protocols, Duration::ZERO)
+            .stale_while_revalidate(false);
+        assert!(cache.protocols().await.is_ok());
+        assert!(cache.networks().await.is_err());
+        assert_eq!(cache.stats().errors, 2);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_entry_names() {
+        let query = |pairs: &[(&str, &str)]| {
+            let pairs = pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>();
+            entry_name(&pairs)
+        };
+
+        assert_eq!(query(&[]), "all");
+        assert_eq!(query(&[("symbol", "a b/c")]), "symbol=a%20b%2Fc");
+        let names = [
+            query(&[("symbol", "a b")]),
+            query(&[("symbol", "a_b")]),
+            query(&[("symbol", "a/b")]),
+            query(&[("a", "b"), ("c", "d")]),
+            query(&[("a", "b_c=d")]),
+            query(&[("all", "")]),
+        ];
+        let distinct = names.iter().collect::<HashSet<_>>();
+        assert_eq!(distinct.len(), names.len());
+    }
+}
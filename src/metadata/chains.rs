@@ -0,0 +1,244 @@
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::networks::Network;
+
+/// Address used by the Enso API for the native currency of every chain, e.g. ETH
+/// on Ethereum.
+pub const NATIVE_TOKEN_ADDRESS: &str = "0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee";
+
+/// Address of the Enso router, deployed at the same address on every well-known
+/// chain.
+pub const ENSO_ROUTER_ADDRESS: &str = "0x80eba3855878739f4710233a8a19d89bdd2ffb8e";
+
+/// Returns `true` if `address` is the [`NATIVE_TOKEN_ADDRESS`].
+pub fn is_native_token(address: &str) -> bool {
+    address.eq_ignore_ascii_case(NATIVE_TOKEN_ADDRESS)
+}
+
+/// The native currency of a chain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NativeCurrency {
+    /// The symbol of the currency, e.g. `ETH`.
+    pub symbol: &'static str,
+    /// The number of decimals of the currency.
+    pub decimals: u8,
+}
+
+/// A chain supported by Enso.
+///
+/// Well-known chains come with their native currency, block explorer and Enso
+/// router. Other chains returned by the API are `Chain::Other`. Chains are
+/// compared by ID, so `Chain::Other(1)` is the same as `Chain::Ethereum`.
+///
+/// # Example
+///
+/// ```
+/// # use enso::{bundle::core::Bundle, metadata::chains::Chain};
+/// let bundle = Bundle::new(Chain::Optimism.id());
+/// let link = Chain::Optimism.address_url("0x4200000000000000000000000000000000000042");
+/// assert_eq!(Chain::from(10), Chain::Optimism);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum Chain {
+    #[default]
+    Ethereum,
+    Optimism,
+    Binance,
+    Gnosis,
+    Polygon,
+    Base,
+    Arbitrum,
+    Avalanche,
+    Linea,
+    /// A chain without built-in metadata, by ID.
+    Other(u32),
+}
+
+impl Chain {
+    /// The well-known chains.
+    pub const KNOWN: [Chain; 9] = [
+        Chain::Ethereum,
+        Chain::Optimism,
+        Chain::Binance,
+        Chain::Gnosis,
+        Chain::Polygon,
+        Chain::Base,
+        Chain::Arbitrum,
+        Chain::Avalanche,
+        Chain::Linea,
+    ];
+
+    /// Returns the ID of the chain.
+    pub fn id(&self) -> u32 {
+        match self {
+            Chain::Ethereum => 1,
+            Chain::Optimism => 10,
+            Chain::Binance => 56,
+            Chain::Gnosis => 100,
+            Chain::Polygon => 137,
+            Chain::Base => 8453,
+            Chain::Arbitrum => 42161,
+            Chain::Avalanche => 43114,
+            Chain::Linea => 59144,
+            Chain::Other(id) => *id,
+        }
+    }
+
+    /// Returns the name of a well-known chain.
+    pub fn name(&self) -> Option<&'static str> {
+        self.info().map(|(name, _, _)| name)
+    }
+
+    /// Returns the native currency of a well-known chain.
+    pub fn native_currency(&self) -> Option<NativeCurrency> {
+        self.info().map(|(_, symbol, _)| NativeCurrency {
+            symbol,
+            decimals: 18,
+        })
+    }
+
+    /// Returns the URL of the block explorer of a well-known chain, e.g.
+    /// `https://etherscan.io`.
+    pub fn explorer_url(&self) -> Option<&'static str> {
+        self.info().map(|(_, _, explorer)| explorer)
+    }
+
+    /// Returns the address of the Enso router on a well-known chain.
+    pub fn router_address(&self) -> Option<&'static str> {
+        self.info().map(|_| ENSO_ROUTER_ADDRESS)
+    }
+
+    /// Returns the explorer link of the transaction `hash`.
+    pub fn tx_url(&self, hash: &str) -> Option<String> {
+        self.explorer_link("tx", hash)
+    }
+
+    /// Returns the explorer link of the account or contract at `address`.
+    pub fn address_url(&self, address: &str) -> Option<String> {
+        self.explorer_link("address", address)
+    }
+
+    /// Returns the explorer link of the token at `address`.
+    pub fn token_url(&self, address: &str) -> Option<String> {
+        self.explorer_link("token", address)
+    }
+
+    fn explorer_link(&self, kind: &str, value: &str) -> Option<String> {
+        Some(format!("{}/{}/{}", self.explorer_url()?, kind, value))
+    }
+
+    fn info(&self) -> Option<(&'static str, &'static str, &'static str)> {
+        Some(match Chain::from(self.id()) {
+            Chain::Ethereum => ("Ethereum", "ETH", "https://etherscan.io"),
+            Chain::Optimism => ("Optimism", "ETH", "https://optimistic.etherscan.io"),
+            Chain::Binance => ("Binance", "BNB", "https://bscscan.com"),
+            Chain::Gnosis => ("Gnosis", "xDAI", "https://gnosisscan.io"),
+            Chain::Polygon => ("Polygon", "POL", "https://polygonscan.com"),
+            Chain::Base => ("Base", "ETH", "https://basescan.org"),
+            Chain::Arbitrum => ("Arbitrum", "ETH", "https://arbiscan.io"),
+            Chain::Avalanche => ("Avalanche", "AVAX", "https://snowtrace.io"),
+            Chain::Linea => ("Linea", "ETH", "https://lineascan.build"),
+            Chain::Other(_) => return None,
+        })
+    }
+}
+
+impl PartialEq for Chain {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Chain {}
+
+impl Hash for Chain {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl From<u32> for Chain {
+    fn from(id: u32) -> Self {
+        Chain::KNOWN
+            .into_iter()
+            .find(|chain| chain.id() == id)
+            .unwrap_or(Chain::Other(id))
+    }
+}
+
+impl From<Chain> for u32 {
+    fn from(chain: Chain) -> Self {
+        chain.id()
+    }
+}
+
+impl Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "Chain {}", self.id()),
+        }
+    }
+}
+
+impl Network {
+    /// Returns the chain of the network.
+    pub fn chain(&self) -> Chain {
+        Chain::from(self.id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::MockServer;
+
+    use super::*;
+
+    #[test]
+    fn test_chain_metadata() {
+        assert_eq!(Chain::from(10), Chain::Optimism);
+        assert_eq!(Chain::from(7777), Chain::Other(7777));
+        assert_eq!(u32::from(Chain::Base), 8453);
+        assert_eq!(Chain::Gnosis.native_currency().unwrap().symbol, "xDAI");
+        assert_eq!(
+            Chain::Arbitrum.tx_url("0xabc").as_deref(),
+            Some("https://arbiscan.io/tx/0xabc")
+        );
+        assert_eq!(Chain::Other(7777).address_url("0xabc"), None);
+        assert_eq!(Chain::Other(7777).to_string(), "Chain 7777");
+        assert_eq!(Chain::Other(1), Chain::Ethereum);
+        assert_eq!(Chain::Other(10).to_string(), "Optimism");
+        assert_eq!(
+            [Chain::Other(137), Chain::Polygon]
+                .into_iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            1
+        );
+        assert!(is_native_token(
+            "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE"
+        ));
+        assert_eq!(
+            serde_json::from_str::<Vec<Chain>>("[1, 5]").unwrap(),
+            vec![Chain::Ethereum, Chain::Other(5)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_network_chains() {
+        let server = MockServer::start();
+        let networks = server.enso().get_networks().await.unwrap();
+
+        for network in networks {
+            let chain = network.chain();
+            assert_eq!(chain.name(), Some(network.name.as_str()));
+            assert!(chain.router_address().is_some());
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod cache;
+pub mod chains;
 pub mod networks;
+pub mod prices;
 pub mod protocols;
+pub mod registry;
+pub mod token_filter;
+pub mod token_list;
 pub mod tokens;
+pub mod underlying;
+pub mod units;
@@ -1,8 +1,6 @@
-use anyhow::{anyhow, Result};
-use reqwest::{header::AUTHORIZATION, Client};
 use serde::{Deserialize, Serialize};
 
-use crate::core::Enso;
+use crate::core::{Endpoint, Enso, Result};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,32 +14,22 @@ impl Enso {
     ///
     /// # Returns
     ///
-    /// A `Result` containing a vector of `Network` instances or an error.
+    /// A `Result` containing a vector of `Network` instances or an `EnsoError`.
+    #[tracing::instrument(skip_all)]
     pub async fn get_networks(&self) -> Result<Vec<Network>> {
-        let client = Client::new();
-        let url = format!("{}/networks", self.get_api_url());
-        let auth = format!("Bearer {}", self.api_key);
-        let response = client.get(&url).header(AUTHORIZATION, auth).send().await;
-        response
-            .map_err(|_| anyhow!("Couldn't get tokens"))?
-            .json::<Vec<Network>>()
-            .await
-            .map_err(|_| anyhow!("Couldn't parse result"))
+        let path = Endpoint::Networks.path();
+        self.get_json(path, &[]).await
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::core::Version;
-
-    use super::*;
+    use crate::testing::MockServer;
 
     #[tokio::test]
     async fn test_get_networks() {
-        let enso = Enso::new(
-            "1e02632d-6feb-4a75-a157-documentation".to_string(),
-            Version::V1,
-        );
+        let server = MockServer::start();
+        let enso = server.enso();
 
         let networks = enso.get_networks().await;
 
@@ -0,0 +1,353 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::core::{Endpoint, Enso, Result};
+
+use super::units::format_units;
+
+/// Maximum number of addresses sent in one `get_prices` request.
+pub const MAX_PRICES_PER_REQUEST: usize = 50;
+
+/// Shortest interval between two polls of a `price_stream`.
+pub const MIN_PRICE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The USD price of a token.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Price {
+    /// The ID of the chain the token lives on.
+    pub chain_id: u32,
+    /// The address of the token.
+    pub address: String,
+    /// The symbol of the token, e.g. `USDC`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// The number of decimals of the token.
+    pub decimals: u8,
+    /// The price of one token, in USD.
+    pub price: f64,
+    /// When the price was observed, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// How reliable the price is, from 0 to 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+impl Price {
+    /// Returns the USD value of a raw on-chain `amount` of the token.
+    ///
+    /// # Returns
+    ///
+    /// The value, or `None` if `amount` isn't a non-negative integer.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let usdc = enso.get_price(1, "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").await?;
+    /// println!("{:?} USD", usdc.value_of("2500000"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn value_of(&self, amount: &str) -> Option<f64> {
+        let amount = format_units(amount, self.decimals)?.parse::<f64>().ok()?;
+        Some(amount * self.price)
+    }
+}
+
+impl Enso {
+    /// Retrieves the price of the token at `address` on the chain `chain_id`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Price` or an `EnsoError`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let weth = enso.get_price(1, "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").await?;
+    /// println!("{} USD", weth.price);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(skip(self))]
+    pub async fn get_price(&self, chain_id: u32, address: &str) -> Result<Price> {
+        let path = Endpoint::Prices.path();
+        let path = format!("{}/{}/{}", path, chain_id, address);
+        self.get_json(&path, &[]).await
+    }
+
+    /// Retrieves the prices of the tokens at `addresses` on the chain `chain_id`.
+    ///
+    /// The addresses are sent in batches of [`MAX_PRICES_PER_REQUEST`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the prices the API knows, in no particular order, or
+    /// an `EnsoError`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    /// # const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let prices = enso.get_prices(1, &[USDC, WETH]).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(skip(self, addresses), fields(count = addresses.len()))]
+    pub async fn get_prices<A: AsRef<str>>(
+        &self,
+        chain_id: u32,
+        addresses: &[A],
+    ) -> Result<Vec<Price>> {
+        let path = Endpoint::Prices.path();
+        let path = format!("{}/{}", path, chain_id);
+        let mut prices = Vec::with_capacity(addresses.len());
+        for batch in addresses.chunks(MAX_PRICES_PER_REQUEST) {
+            let query = batch
+                .iter()
+                .map(|address| ("addresses".to_string(), address.as_ref().to_string()))
+                .collect::<Vec<_>>();
+            prices.extend(self.get_json::<Vec<Price>>(&path, &query).await?);
+        }
+        Ok(prices)
+    }
+
+    /// Streams the prices of the tokens at `addresses`, fetched right away and
+    /// then every `every`, raised to `MIN_PRICE_POLL_INTERVAL` if shorter.
+    ///
+    /// A failed poll is yielded as an error and the stream keeps polling.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use futures::StreamExt;
+    /// # const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let mut prices = enso.price_stream(1, vec![WETH.to_string()], Duration::from_secs(30));
+    /// while let Some(prices) = prices.next().await {
+    ///     println!("{:?}", prices?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn price_stream(
+        &self,
+        chain_id: u32,
+        addresses: Vec<String>,
+        every: Duration,
+    ) -> Pin<Box<dyn Stream<Item = Result<Vec<Price>>> + Send>> {
+        let every = every.max(MIN_PRICE_POLL_INTERVAL);
+        Box::pin(stream::unfold(
+            (self.clone(), addresses, None),
+            move |(enso, addresses, ticks)| async move {
+                // Created on the first poll, since it needs a Tokio runtime.
+                let mut ticks = ticks.unwrap_or_else(|| {
+                    let mut ticks = interval(every);
+                    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    ticks
+                });
+                ticks.tick().await;
+                let prices = enso.get_prices(chain_id, &addresses).await;
+                Some((prices, (enso, addresses, Some(ticks))))
+            },
+        ))
+    }
+}
+
+/// In-memory cache of token prices, shared by its clones.
+///
+/// Prices older than the TTL are fetched again, and `get_many` fetches the
+/// missing prices with batched requests. Tokens the API has no price for are
+/// remembered for the TTL as well, so they aren't requested on every call.
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use enso::metadata::prices::PriceCache;
+/// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+/// let prices = PriceCache::new(enso.clone(), Duration::from_secs(60));
+/// let weth = prices.get(1, "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PriceCache {
+    enso: Enso,
+    ttl: Duration,
+    prices: Arc<Mutex<CachedPrices>>,
+}
+
+/// Prices keyed by chain and lowercase address, with when they were fetched.
+/// `None` marks a token the API has no price for.
+type CachedPrices = HashMap<(u32, String), (Instant, Option<Price>)>;
+
+impl PriceCache {
+    /// Creates an empty cache keeping prices for `ttl`.
+    pub fn new(enso: Enso, ttl: Duration) -> PriceCache {
+        PriceCache {
+            enso,
+            ttl,
+            prices: Arc::default(),
+        }
+    }
+
+    /// Returns the cached price of a token, even expired, without fetching it.
+    pub fn cached(&self, chain_id: u32, address: &str) -> Option<Price> {
+        let prices = self.prices.lock().unwrap();
+        let (_, price) = prices.get(&(chain_id, address.to_lowercase()))?;
+        price.clone()
+    }
+
+    /// Returns the price of a token, fetching it if missing or expired.
+    pub async fn get(&self, chain_id: u32, address: &str) -> Result<Price> {
+        if let Some(Some(price)) = self.fresh(chain_id, address) {
+            return Ok(price);
+        }
+        let price = self.enso.get_price(chain_id, address).await?;
+        self.store(chain_id, &[address], vec![price.clone()]);
+        Ok(price)
+    }
+
+    /// Returns the prices of tokens, fetching the missing or expired ones.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the prices in the order of `addresses`, without the
+    /// tokens the API has no price for.
+    pub async fn get_many<A: AsRef<str>>(
+        &self,
+        chain_id: u32,
+        addresses: &[A],
+    ) -> Result<Vec<Price>> {
+        let missing = addresses
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|address| self.fresh(chain_id, address).is_none())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let prices = self.enso.get_prices(chain_id, &missing).await?;
+            self.store(chain_id, &missing, prices);
+        }
+        Ok(addresses
+            .iter()
+            .filter_map(|address| self.cached(chain_id, address.as_ref()))
+            .collect())
+    }
+
+    /// Removes every cached price.
+    pub fn clear(&self) {
+        self.prices.lock().unwrap().clear();
+    }
+
+    /// Returns the unexpired entry of a token, `Some(None)` if it has no price.
+    fn fresh(&self, chain_id: u32, address: &str) -> Option<Option<Price>> {
+        let prices = self.prices.lock().unwrap();
+        let (fetched_at, price) = prices.get(&(chain_id, address.to_lowercase()))?;
+        (fetched_at.elapsed() < self.ttl).then(|| price.clone())
+    }
+
+    /// Stores the `prices` fetched for `requested`, marking the tokens without
+    /// a price.
+    fn store(&self, chain_id: u32, requested: &[&str], prices: Vec<Price>) {
+        let now = Instant::now();
+        let mut cached = self.prices.lock().unwrap();
+        for address in requested {
+            cached.insert((chain_id, address.to_lowercase()), (now, None));
+        }
+        for price in prices {
+            cached.insert((chain_id, price.address.to_lowercase()), (now, Some(price)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use crate::testing::MockServer;
+
+    use super::*;
+
+    const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const WETH: &str = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+
+    #[tokio::test]
+    async fn test_get_prices() {
+        let server = MockServer::start();
+        let enso = server.enso();
+
+        let usdc = enso.get_price(1, USDC).await.unwrap();
+        assert_eq!(usdc.symbol.as_deref(), Some("USDC"));
+        assert_eq!(usdc.value_of("2500000"), Some(2.5));
+        assert!(enso.get_price(1, "0xdead").await.is_err());
+
+        let addresses = std::iter::repeat_n(WETH, MAX_PRICES_PER_REQUEST)
+            .chain([USDC, "0xdead"])
+            .collect::<Vec<_>>();
+        let prices = enso.get_prices(1, &addresses).await.unwrap();
+        assert_eq!(prices.len(), MAX_PRICES_PER_REQUEST + 1);
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_price_cache() {
+        let server = MockServer::start();
+        let cache = PriceCache::new(server.enso(), Duration::from_secs(60));
+
+        let weth = cache.get(1, WETH).await.unwrap();
+        let prices = cache.get_many(1, &[USDC, "0xdead", WETH]).await.unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[1], weth);
+        cache.get_many(1, &[WETH, USDC, "0xdead"]).await.unwrap();
+        assert_eq!(server.requests().len(), 2);
+        assert!(cache.cached(1, "0xdead").is_none());
+        assert_eq!(
+            server.requests()[1].query,
+            vec![
+                ("addresses".to_string(), USDC.to_string()),
+                ("addresses".to_string(), "0xdead".to_string())
+            ]
+        );
+
+        let expired = PriceCache::new(server.enso(), Duration::ZERO);
+        expired.get(1, USDC).await.unwrap();
+        expired.get(1, USDC).await.unwrap();
+        assert_eq!(server.requests().len(), 4);
+        assert!(expired.cached(1, USDC).is_some());
+    }
+
+    #[test]
+    fn test_price_stream() {
+        let server = MockServer::start();
+        // Created outside of a runtime, with an interval below the minimum.
+        let stream = server
+            .enso()
+            .price_stream(1, vec![USDC.to_string()], Duration::ZERO);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let start = Instant::now();
+        let polls = runtime.block_on(stream.take(2).collect::<Vec<_>>());
+
+        assert!(start.elapsed() >= MIN_PRICE_POLL_INTERVAL);
+        assert_eq!(polls.len(), 2);
+        assert!(polls
+            .iter()
+            .all(|prices| prices.as_ref().unwrap().len() == 1));
+    }
+}
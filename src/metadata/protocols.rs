@@ -1,20 +1,57 @@
-use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
-use reqwest::{header::AUTHORIZATION, Client};
 use serde::{Deserialize, Serialize};
 
-use crate::core::Enso;
+use crate::core::{Endpoint, Enso, Result};
+
+use super::networks::Network;
 
 pub static ENSO_PROTOCOL: Lazy<Protocol> = Lazy::new(|| Protocol {
     slug: "enso".to_string(),
     url: "https://api.enso.finance".to_string(),
+    name: Some("Enso".to_string()),
+    description: None,
+    logos_uri: None,
+    chains: Vec::new(),
 });
 
+/// A protocol Enso can interact with.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Protocol {
+    /// The identifier of the protocol, e.g. `aave-v3`.
     pub slug: String,
+    /// The website of the protocol.
     pub url: String,
+    /// The name of the protocol, e.g. `Aave V3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// A short description of the protocol.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// URLs of the logos of the protocol.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logos_uri: Option<Vec<String>>,
+    /// The networks the protocol is deployed on. Empty when unknown.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chains: Vec<Network>,
+}
+
+impl Protocol {
+    /// Returns the name of the protocol, or its slug if the name is missing.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.slug)
+    }
+
+    /// Returns the IDs of the chains the protocol is deployed on.
+    pub fn chain_ids(&self) -> Vec<u32> {
+        self.chains.iter().map(|chain| chain.id).collect()
+    }
+
+    /// Returns `true` if the protocol is deployed on the chain `chain_id`, or if
+    /// its chains are unknown.
+    pub fn supports_chain(&self, chain_id: u32) -> bool {
+        self.chains.is_empty() || self.chains.iter().any(|chain| chain.id == chain_id)
+    }
 }
 
 impl Enso {
@@ -22,41 +59,79 @@ impl Enso {
     ///
     /// # Returns
     ///
-    /// A `Result` containing a vector of `Protocol` instances or an error.
+    /// A `Result` containing a vector of `Protocol` instances or an `EnsoError`.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
     /// let protocols = enso.get_protocols().await?;
+    /// # Ok(())
+    /// # }
     /// ```
+    #[tracing::instrument(skip(self))]
     pub async fn get_protocols(&self) -> Result<Vec<Protocol>> {
-        let client = Client::new();
-        let url = format!("{}/protocols", self.get_api_url());
-        let auth = format!("Bearer {}", self.api_key);
-        let response = client.get(&url).header(AUTHORIZATION, auth).send().await;
-        response
-            .map_err(|_| anyhow!("Couldn't get tokens"))?
-            .json::<Vec<Protocol>>()
-            .await
-            .map_err(|_| anyhow!("Couldn't parse result"))
+        let path = Endpoint::Protocols.path();
+        self.get_json(path, &[]).await
+    }
+
+    /// Retrieves the protocols deployed on the chain `chain_id`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a vector of `Protocol` instances or an `EnsoError`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::chains::Chain;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let protocols = enso.get_protocols_by_chain(Chain::Arbitrum.id()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(skip(self))]
+    pub async fn get_protocols_by_chain(&self, chain_id: u32) -> Result<Vec<Protocol>> {
+        let path = Endpoint::Protocols.path();
+        let query = [("chainId".to_string(), chain_id.to_string())];
+        self.get_json(path, &query).await
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::core::Version;
-
-    use super::*;
+    use crate::{metadata::chains::Chain, testing::MockServer};
 
     #[tokio::test]
     async fn test_get_protocols() {
-        let enso = Enso::new(
-            "1e02632d-6feb-4a75-a157-documentation".to_string(),
-            Version::V1,
-        );
+        let server = MockServer::start();
+        let enso = server.enso();
 
         let projects = enso.get_protocols().await;
 
         assert!(projects.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_get_protocols_of_chain() {
+        let server = MockServer::start();
+        let enso = server.enso();
+
+        let protocols = enso
+            .get_protocols_by_chain(Chain::Arbitrum.id())
+            .await
+            .unwrap();
+
+        let slugs = protocols
+            .iter()
+            .map(|protocol| protocol.slug.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slugs,
+            vec!["enso", "aave-v3", "compound-v3", "curve", "uniswap-v2"]
+        );
+        assert!(protocols.iter().all(|p| p.supports_chain(42161)));
+        assert_eq!(protocols[1].display_name(), "Aave V3");
+        assert_eq!(server.requests()[0].query_param("chainId"), Some("42161"));
+    }
 }
@@ -0,0 +1,402 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use futures::StreamExt;
+
+use crate::core::{Enso, IntoQuery, Result};
+
+use super::tokens::Token;
+
+/// Key of a token in a `TokenRegistry`: its chain id and lowercase address.
+type TokenKey = (u32, String);
+
+/// In-memory index of tokens, built from the token stream.
+///
+/// Tokens are indexed by chain and address, symbol, protocol slug, primary
+/// address and underlying address. Addresses and symbols are compared
+/// case-insensitively.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::metadata::token_filter::TokenFilter;
+/// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+/// let registry = enso
+///     .token_registry(TokenFilter::new().chain_id(1).include_metadata(true))
+///     .await?;
+/// let usdc = registry.by_symbol("usdc");
+/// let pools = registry.wrapping(1, "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TokenRegistry {
+    tokens: BTreeMap<TokenKey, Token>,
+    by_symbol: HashMap<String, BTreeSet<TokenKey>>,
+    by_protocol: HashMap<String, BTreeSet<TokenKey>>,
+    by_primary_address: HashMap<TokenKey, BTreeSet<TokenKey>>,
+    by_underlying: HashMap<TokenKey, BTreeSet<TokenKey>>,
+}
+
+/// Number of tokens changed by a `TokenRegistry` refresh.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistryUpdate {
+    /// Tokens that weren't in the registry.
+    pub added: usize,
+    /// Tokens whose data changed.
+    pub updated: usize,
+    /// Tokens that were already up to date.
+    pub unchanged: usize,
+}
+
+fn key(chain_id: u32, address: &str) -> TokenKey {
+    (chain_id, address.to_lowercase())
+}
+
+impl TokenRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> TokenRegistry {
+        TokenRegistry::default()
+    }
+
+    /// Returns the number of tokens in the registry.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns `true` if the registry doesn't hold any token.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Iterates over the tokens, ordered by chain and address.
+    pub fn iter(&self) -> impl Iterator<Item = &Token> {
+        self.tokens.values()
+    }
+
+    /// Adds `token`, replacing the token with the same chain and address.
+    ///
+    /// # Returns
+    ///
+    /// The replaced token, if any.
+    pub fn insert(&mut self, token: Token) -> Option<Token> {
+        let key = key(token.chain_id, &token.address);
+        let previous = self.remove(key.0, &key.1);
+
+        if let Some(symbol) = &token.symbol {
+            self.by_symbol
+                .entry(symbol.to_lowercase())
+                .or_default()
+                .insert(key.clone());
+        }
+        self.by_protocol
+            .entry(token.protocol_slug.clone())
+            .or_default()
+            .insert(key.clone());
+        self.by_primary_address
+            .entry(self::key(token.chain_id, &token.primary_address))
+            .or_default()
+            .insert(key.clone());
+        for underlying in &token.underlying_tokens {
+            self.by_underlying
+                .entry(self::key(token.chain_id, underlying))
+                .or_default()
+                .insert(key.clone());
+        }
+        self.tokens.insert(key, token);
+        previous
+    }
+
+    /// Removes the token at `address` on the chain `chain_id`.
+    ///
+    /// # Returns
+    ///
+    /// The removed token, if any.
+    pub fn remove(&mut self, chain_id: u32, address: &str) -> Option<Token> {
+        let key = key(chain_id, address);
+        let token = self.tokens.remove(&key)?;
+
+        fn unindex<K: std::hash::Hash + Eq>(
+            index: &mut HashMap<K, BTreeSet<TokenKey>>,
+            entry: K,
+            key: &TokenKey,
+        ) {
+            if let Some(keys) = index.get_mut(&entry) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    index.remove(&entry);
+                }
+            }
+        }
+        if let Some(symbol) = &token.symbol {
+            unindex(&mut self.by_symbol, symbol.to_lowercase(), &key);
+        }
+        unindex(&mut self.by_protocol, token.protocol_slug.clone(), &key);
+        unindex(
+            &mut self.by_primary_address,
+            self::key(chain_id, &token.primary_address),
+            &key,
+        );
+        for underlying in &token.underlying_tokens {
+            unindex(
+                &mut self.by_underlying,
+                self::key(chain_id, underlying),
+                &key,
+            );
+        }
+        Some(token)
+    }
+
+    /// Adds or replaces every token of `tokens`.
+    ///
+    /// # Returns
+    ///
+    /// How many tokens were added, updated or already up to date.
+    pub fn upsert<I: IntoIterator<Item = Token>>(&mut self, tokens: I) -> RegistryUpdate {
+        let mut update = RegistryUpdate::default();
+        for token in tokens {
+            match self.get(token.chain_id, &token.address) {
+                Some(existing) if *existing == token => update.unchanged += 1,
+                Some(_) => {
+                    self.insert(token);
+                    update.updated += 1;
+                }
+                None => {
+                    self.insert(token);
+                    update.added += 1;
+                }
+            }
+        }
+        update
+    }
+
+    /// Streams the tokens matching `params` and adds or replaces them in the
+    /// registry, page by page. Tokens missing from the stream are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `enso` - The client used to stream the tokens.
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing how many tokens were added, updated or already up to
+    /// date. Tokens of the pages received before an error are kept.
+    pub async fn refresh(&mut self, enso: &Enso, params: impl IntoQuery) -> Result<RegistryUpdate> {
+        let mut pages = enso.tokens_pages(params);
+        let mut update = RegistryUpdate::default();
+        while let Some(page) = pages.next().await {
+            let page_update = self.upsert(page?.items);
+            update.added += page_update.added;
+            update.updated += page_update.updated;
+            update.unchanged += page_update.unchanged;
+        }
+        Ok(update)
+    }
+
+    /// Returns the token at `address` on the chain `chain_id`.
+    pub fn get(&self, chain_id: u32, address: &str) -> Option<&Token> {
+        self.tokens.get(&key(chain_id, address))
+    }
+
+    /// Returns the tokens with the given symbol, compared case-insensitively.
+    pub fn by_symbol(&self, symbol: &str) -> Vec<&Token> {
+        self.lookup(self.by_symbol.get(&symbol.to_lowercase()))
+    }
+
+    /// Returns the tokens of the protocol `slug`.
+    pub fn by_protocol(&self, slug: &str) -> Vec<&Token> {
+        self.lookup(self.by_protocol.get(slug))
+    }
+
+    /// Returns the tokens whose primary address is `address` on the chain `chain_id`.
+    pub fn by_primary_address(&self, chain_id: u32, address: &str) -> Vec<&Token> {
+        self.lookup(self.by_primary_address.get(&key(chain_id, address)))
+    }
+
+    /// Returns the tokens wrapping the token at `address` on the chain `chain_id`.
+    pub fn wrapping(&self, chain_id: u32, address: &str) -> Vec<&Token> {
+        self.lookup(self.by_underlying.get(&key(chain_id, address)))
+    }
+
+    /// Searches tokens by symbol, tolerating typos.
+    ///
+    /// Exact matches come first, then symbols starting with `query`, then symbols
+    /// containing it, then symbols within two edits of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The symbol to look for, compared case-insensitively.
+    /// * `limit` - The maximum number of tokens to return.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Token> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = self
+            .by_symbol
+            .iter()
+            .filter_map(|(symbol, keys)| {
+                let score = if *symbol == query {
+                    0
+                } else if symbol.starts_with(&query) {
+                    1
+                } else if symbol.contains(&query) {
+                    2
+                } else {
+                    match edit_distance(symbol, &query) {
+                        distance @ 1..=2 => 2 + distance,
+                        _ => return None,
+                    }
+                };
+                Some((score, symbol, keys))
+            })
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches
+            .into_iter()
+            .flat_map(|(_, _, keys)| keys.iter().filter_map(|key| self.tokens.get(key)))
+            .take(limit)
+            .collect()
+    }
+
+    fn lookup(&self, keys: Option<&BTreeSet<TokenKey>>) -> Vec<&Token> {
+        keys.into_iter()
+            .flatten()
+            .filter_map(|key| self.tokens.get(key))
+            .collect()
+    }
+}
+
+impl FromIterator<Token> for TokenRegistry {
+    fn from_iter<I: IntoIterator<Item = Token>>(tokens: I) -> Self {
+        let mut registry = TokenRegistry::new();
+        registry.upsert(tokens);
+        registry
+    }
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl Enso {
+    /// Streams the tokens matching the provided parameters into a `TokenRegistry`.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the registry.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::token_filter::TokenFilter;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let registry = enso
+    ///     .token_registry(TokenFilter::new().chain_id(10).include_metadata(true))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn token_registry(&self, params: impl IntoQuery) -> Result<TokenRegistry> {
+        let mut registry = TokenRegistry::new();
+        registry.refresh(self, params).await?;
+        Ok(registry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{metadata::token_filter::TokenFilter, testing::MockServer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registry_indexes() {
+        let server = MockServer::start();
+        let enso = server.enso();
+        let mut registry = enso
+            .token_registry(TokenFilter::new().include_metadata(true))
+            .await
+            .unwrap();
+
+        assert_eq!(registry.len(), 11);
+        assert!(registry
+            .get(10, "0x0B2C639C533813F4AA9D7837CAF62653D097FF85")
+            .is_some());
+        assert_eq!(registry.by_symbol("usdc").len(), 2);
+        assert_eq!(registry.by_protocol("aave-v3").len(), 2);
+        assert_eq!(
+            registry
+                .wrapping(10, "0x0b2c639c533813f4aa9d7837caf62653d097ff85")
+                .len(),
+            2
+        );
+        assert_eq!(
+            registry
+                .by_primary_address(1, "0xae7ab96520de3a18e5e111b5eaab095312d7fe84")
+                .len(),
+            1
+        );
+
+        let update = registry
+            .refresh(&enso, TokenFilter::new().chain_id(1).include_metadata(true))
+            .await
+            .unwrap();
+        assert_eq!(
+            update,
+            RegistryUpdate {
+                added: 0,
+                updated: 0,
+                unchanged: 4,
+            }
+        );
+
+        let mut steth = registry
+            .remove(1, "0xae7ab96520de3a18e5e111b5eaab095312d7fe84")
+            .unwrap();
+        assert!(registry.by_symbol("steth").is_empty());
+        steth.symbol = Some("wstETH".to_string());
+        registry.insert(steth);
+        assert_eq!(registry.by_symbol("WSTETH").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let server = MockServer::start();
+        let registry = server
+            .enso()
+            .token_registry(TokenFilter::new().chain_id(10).include_metadata(true))
+            .await
+            .unwrap();
+
+        let symbols = |query: &str| {
+            registry
+                .search(query, 10)
+                .into_iter()
+                .map(|token| token.display_name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            symbols("weth"),
+            vec!["WETH", "aOptWETH", "vAMMV2-USDC/WETH"]
+        );
+        assert_eq!(symbols("usdx"), vec!["USDC", "sUSD"]);
+        assert!(symbols("").is_empty());
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
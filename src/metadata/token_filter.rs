@@ -0,0 +1,204 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::IntoQuery;
+
+use super::tokens::Token;
+
+/// Kind of token returned by `/tokens`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenType {
+    /// A plain token, e.g. an ERC-20 like USDC.
+    Base,
+    /// A position token of a DeFi protocol, e.g. an LP or vault share.
+    Defi,
+}
+
+impl Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TokenType::Base => "base",
+                TokenType::Defi => "defi",
+            }
+        )
+    }
+}
+
+/// Typed filter for the `/tokens` endpoint, accepted by every token method.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::metadata::token_filter::{TokenFilter, TokenType};
+/// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+/// let filter = TokenFilter::new()
+///     .chain_id(1)
+///     .protocol_slug("aave-v3")
+///     .token_type(TokenType::Defi);
+/// let (meta, tokens) = enso.get_tokens(&filter).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenFilter {
+    chain_id: Option<u32>,
+    protocol_slug: Option<String>,
+    token_type: Option<TokenType>,
+    address: Option<String>,
+    underlying_token: Option<String>,
+    primary_address: Option<String>,
+    include_metadata: bool,
+    page_size: Option<u32>,
+    page: Option<u32>,
+}
+
+impl TokenFilter {
+    /// Creates a filter matching every token.
+    pub fn new() -> TokenFilter {
+        TokenFilter::default()
+    }
+
+    /// Keeps the tokens of the chain `chain_id`.
+    pub fn chain_id(mut self, chain_id: u32) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Keeps the tokens of the protocol `slug`, e.g. `aave-v3`.
+    pub fn protocol_slug<T: ToString>(mut self, slug: T) -> Self {
+        self.protocol_slug = Some(slug.to_string());
+        self
+    }
+
+    /// Keeps the tokens of the given type.
+    pub fn token_type(mut self, token_type: TokenType) -> Self {
+        self.token_type = Some(token_type);
+        self
+    }
+
+    /// Keeps the token with the given address.
+    pub fn address<T: ToString>(mut self, address: T) -> Self {
+        self.address = Some(address.to_string());
+        self
+    }
+
+    /// Keeps the tokens wrapping the token at `address`.
+    pub fn underlying_token<T: ToString>(mut self, address: T) -> Self {
+        self.underlying_token = Some(address.to_string());
+        self
+    }
+
+    /// Keeps the tokens whose primary address is `address`.
+    pub fn primary_address<T: ToString>(mut self, address: T) -> Self {
+        self.primary_address = Some(address.to_string());
+        self
+    }
+
+    /// Asks the API to include the name, symbol and decimals of the tokens.
+    pub fn include_metadata(mut self, include: bool) -> Self {
+        self.include_metadata = include;
+        self
+    }
+
+    /// Sets the number of tokens returned per page.
+    pub fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Sets the page to fetch, starting at 1. Ignored by the token streams.
+    pub fn page(mut self, page: u32) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    /// Returns `true` if `token` matches the filter. Addresses are compared
+    /// case-insensitively, and the pagination and metadata settings are ignored.
+    pub fn matches(&self, token: &Token) -> bool {
+        let same = |a: &str, b: &str| a.eq_ignore_ascii_case(b);
+        self.chain_id.is_none_or(|id| token.chain_id == id)
+            && self
+                .protocol_slug
+                .as_ref()
+                .is_none_or(|slug| *slug == token.protocol_slug)
+            && self
+                .token_type
+                .is_none_or(|kind| kind.to_string() == token.kind)
+            && self
+                .address
+                .as_ref()
+                .is_none_or(|address| same(address, &token.address))
+            && self.underlying_token.as_ref().is_none_or(|address| {
+                token
+                    .underlying_tokens
+                    .iter()
+                    .any(|underlying| same(address, underlying))
+            })
+            && self
+                .primary_address
+                .as_ref()
+                .is_none_or(|address| same(address, &token.primary_address))
+    }
+}
+
+impl IntoQuery for &TokenFilter {
+    fn into_query(self) -> Vec<(String, String)> {
+        let params = [
+            ("chainId", self.chain_id.map(|id| id.to_string())),
+            ("protocolSlug", self.protocol_slug.clone()),
+            ("type", self.token_type.map(|kind| kind.to_string())),
+            ("address", self.address.clone()),
+            ("underlyingTokens", self.underlying_token.clone()),
+            ("primaryAddress", self.primary_address.clone()),
+            (
+                "includeMetadata",
+                self.include_metadata.then(|| "true".to_string()),
+            ),
+            ("pageSize", self.page_size.map(|size| size.to_string())),
+            ("page", self.page.map(|page| page.to_string())),
+        ];
+        params
+            .into_iter()
+            .filter_map(|(f, v)| v.map(|v| (f.to_string(), v)))
+            .collect()
+    }
+}
+
+impl IntoQuery for TokenFilter {
+    fn into_query(self) -> Vec<(String, String)> {
+        (&self).into_query()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_into_query() {
+        let query = TokenFilter::new()
+            .chain_id(10)
+            .token_type(TokenType::Defi)
+            .underlying_token("0xabc")
+            .include_metadata(true)
+            .page_size(50)
+            .into_query();
+
+        assert_eq!(
+            query,
+            vec![
+                ("chainId".to_string(), "10".to_string()),
+                ("type".to_string(), "defi".to_string()),
+                ("underlyingTokens".to_string(), "0xabc".to_string()),
+                ("includeMetadata".to_string(), "true".to_string()),
+                ("pageSize".to_string(), "50".to_string()),
+            ]
+        );
+        assert!(TokenFilter::new().into_query().is_empty());
+    }
+}
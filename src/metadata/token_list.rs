@@ -0,0 +1,527 @@
+//! Export and import of tokens in the [Token Lists](https://tokenlists.org) format,
+//! CSV and newline-delimited JSON.
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::{parse_json, EnsoError, Result};
+
+use super::{
+    registry::{RegistryUpdate, TokenRegistry},
+    token_filter::TokenFilter,
+    tokens::Token,
+};
+
+/// A token list document following the Token Lists JSON schema.
+///
+/// Enso specific data, such as the protocol and the underlying tokens, is kept in
+/// the `extensions` of every token so a list can be imported back without losses.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::metadata::{registry::TokenRegistry, token_filter::TokenFilter};
+/// # fn example(registry: TokenRegistry) -> Result<(), Box<dyn std::error::Error>> {
+/// let list = registry.export_token_list("Optimism DeFi", &TokenFilter::new().chain_id(10))?;
+/// std::fs::write("optimism.tokenlist.json", list.to_json())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenList {
+    pub name: String,
+    pub timestamp: String,
+    pub version: TokenListVersion,
+    pub tokens: Vec<TokenListEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    #[serde(rename = "logoURI", default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+}
+
+/// Maximum length of the name of a `TokenList`.
+pub const MAX_TOKEN_LIST_NAME_LENGTH: usize = 30;
+
+/// Maximum number of tokens of a `TokenList`.
+pub const MAX_TOKEN_LIST_TOKENS: usize = 10_000;
+
+/// Maximum length of the symbol of a `TokenListEntry`.
+pub const MAX_TOKEN_SYMBOL_LENGTH: usize = 20;
+
+/// Maximum length of the name of a `TokenListEntry`.
+pub const MAX_TOKEN_NAME_LENGTH: usize = 60;
+
+/// Semantic version of a `TokenList`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TokenListVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+/// A token of a `TokenList`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenListEntry {
+    pub chain_id: u32,
+    pub address: String,
+    pub decimals: u8,
+    pub name: String,
+    pub symbol: String,
+    #[serde(rename = "logoURI", default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, Value>,
+}
+
+impl TokenListEntry {
+    /// Converts a token into an entry.
+    ///
+    /// # Returns
+    ///
+    /// The entry, or `None` if the token lacks the decimals, name or symbol
+    /// required by the schema.
+    pub fn from_token(token: &Token) -> Option<TokenListEntry> {
+        let mut extensions = BTreeMap::new();
+        extensions.insert("type".to_string(), Value::from(token.kind.clone()));
+        extensions.insert(
+            "protocolSlug".to_string(),
+            Value::from(token.protocol_slug.clone()),
+        );
+        extensions.insert(
+            "primaryAddress".to_string(),
+            Value::from(token.primary_address.clone()),
+        );
+        // The schema only allows scalar extension values.
+        if !token.underlying_tokens.is_empty() {
+            extensions.insert(
+                "underlyingTokens".to_string(),
+                Value::from(token.underlying_tokens.join(",")),
+            );
+        }
+        if let Some(apy) = token.apy {
+            extensions.insert("apy".to_string(), Value::from(apy));
+        }
+        if let Some(tvl) = token.tvl {
+            extensions.insert("tvl".to_string(), Value::from(tvl));
+        }
+
+        Some(TokenListEntry {
+            chain_id: token.chain_id,
+            address: token.address.clone(),
+            decimals: token.decimals?,
+            name: token.name.clone()?,
+            symbol: token.symbol.clone()?,
+            logo_uri: token
+                .logos_uri
+                .as_ref()
+                .and_then(|logos| logos.first().cloned()),
+            extensions,
+        })
+    }
+
+    /// Checks the entry against the Token Lists schema: a `0x` address of 20
+    /// bytes, a positive chain ID, a symbol of at most 20 characters without
+    /// spaces and a name of at most 60 characters on a single line.
+    ///
+    /// # Returns
+    ///
+    /// An `EnsoError::InvalidTokenList` describing the first rule broken.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |rule: &str| {
+            Err(EnsoError::InvalidTokenList(format!(
+                "token {} on chain {}: {}",
+                self.address, self.chain_id, rule
+            )))
+        };
+        let address = self.address.strip_prefix("0x").unwrap_or_default();
+        if address.len() != 40 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+            return invalid("the address must be 0x followed by 40 hex digits");
+        }
+        if self.chain_id == 0 {
+            return invalid("the chain ID must be positive");
+        }
+        if self.symbol.chars().count() > MAX_TOKEN_SYMBOL_LENGTH
+            || self.symbol.chars().any(char::is_whitespace)
+        {
+            return invalid("the symbol must have at most 20 characters and no spaces");
+        }
+        if self.name.chars().count() > MAX_TOKEN_NAME_LENGTH
+            || self.name.chars().any(|c| c.is_whitespace() && c != ' ')
+        {
+            return invalid("the name must have at most 60 characters on a single line");
+        }
+        Ok(())
+    }
+
+    /// Converts the entry back into a token, reading the Enso data from the
+    /// extensions when present.
+    pub fn to_token(&self) -> Token {
+        let extension = |name: &str| self.extensions.get(name).and_then(Value::as_str);
+        Token {
+            chain_id: self.chain_id,
+            address: self.address.clone(),
+            kind: extension("type").unwrap_or("base").to_string(),
+            protocol_slug: extension("protocolSlug").unwrap_or_default().to_string(),
+            underlying_tokens: extension("underlyingTokens")
+                .filter(|tokens| !tokens.is_empty())
+                .map(|tokens| tokens.split(',').map(str::to_string).collect())
+                .unwrap_or_default(),
+            primary_address: extension("primaryAddress")
+                .unwrap_or(&self.address)
+                .to_string(),
+            symbol: Some(self.symbol.clone()),
+            name: Some(self.name.clone()),
+            decimals: Some(self.decimals),
+            logos_uri: self.logo_uri.clone().map(|logo| vec![logo]),
+            apy: self.extensions.get("apy").and_then(Value::as_f64),
+            tvl: self.extensions.get("tvl").and_then(Value::as_f64),
+        }
+    }
+}
+
+impl TokenList {
+    /// Creates a version 1.0.0 list named `name`, timestamped now.
+    ///
+    /// Tokens without the decimals, name or symbol required by the schema are
+    /// skipped, so the tokens should be fetched with their metadata. Tokens
+    /// breaking the rules of [`TokenListEntry::validate`] are skipped with a
+    /// warning.
+    pub fn new<'a, I: IntoIterator<Item = &'a Token>>(name: &str, tokens: I) -> TokenList {
+        TokenList {
+            name: name.to_string(),
+            timestamp: iso8601(SystemTime::now()),
+            version: TokenListVersion {
+                major: 1,
+                minor: 0,
+                patch: 0,
+            },
+            tokens: tokens
+                .into_iter()
+                .filter_map(TokenListEntry::from_token)
+                .filter(|entry| match entry.validate() {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Skipping token");
+                        false
+                    }
+                })
+                .collect(),
+            keywords: Vec::new(),
+            logo_uri: None,
+        }
+    }
+
+    /// Parses a token list document.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the list, an `EnsoError::Deserialize` if the document
+    /// isn't a token list, or an `EnsoError::InvalidTokenList` if it doesn't pass
+    /// [`TokenList::validate`].
+    pub fn from_json(json: &str) -> Result<TokenList> {
+        let list = parse_json::<TokenList>(json.as_bytes())?;
+        list.validate()?;
+        Ok(list)
+    }
+
+    /// Checks the list against the Token Lists schema: a name of 1 to 30 letters,
+    /// digits, underscores or spaces, 1 to 10,000 tokens, and valid tokens, see
+    /// [`TokenListEntry::validate`]. The decimals are checked when parsing.
+    ///
+    /// # Returns
+    ///
+    /// An `EnsoError::InvalidTokenList` describing the first rule broken.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |rule: String| Err(EnsoError::InvalidTokenList(rule));
+        if self.name.is_empty()
+            || self.name.chars().count() > MAX_TOKEN_LIST_NAME_LENGTH
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ' ')
+        {
+            return invalid(format!(
+                "the name {:?} must have 1 to 30 letters, digits, underscores or spaces",
+                self.name
+            ));
+        }
+        if self.tokens.is_empty() || self.tokens.len() > MAX_TOKEN_LIST_TOKENS {
+            return invalid(format!(
+                "the list must have 1 to 10000 tokens, not {}",
+                self.tokens.len()
+            ));
+        }
+        self.tokens.iter().try_for_each(TokenListEntry::validate)
+    }
+
+    /// Serializes the list as a pretty-printed JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A token list is always serializable")
+    }
+
+    /// Returns the tokens of the list.
+    pub fn to_tokens(&self) -> Vec<Token> {
+        self.tokens.iter().map(TokenListEntry::to_token).collect()
+    }
+}
+
+/// Serializes tokens as CSV, with a header row.
+///
+/// Underlying tokens are separated by `;` and missing values are left empty.
+pub fn tokens_to_csv<'a, I: IntoIterator<Item = &'a Token>>(tokens: I) -> String {
+    let mut csv = String::from(
+        "chainId,address,type,protocolSlug,symbol,name,decimals,primaryAddress,underlyingTokens,apy,tvl\n",
+    );
+    for token in tokens {
+        let fields = [
+            token.chain_id.to_string(),
+            token.address.clone(),
+            token.kind.clone(),
+            token.protocol_slug.clone(),
+            token.symbol.clone().unwrap_or_default(),
+            token.name.clone().unwrap_or_default(),
+            token.decimals.map(|d| d.to_string()).unwrap_or_default(),
+            token.primary_address.clone(),
+            token.underlying_tokens.join(";"),
+            token.apy.map(|apy| apy.to_string()).unwrap_or_default(),
+            token.tvl.map(|tvl| tvl.to_string()).unwrap_or_default(),
+        ];
+        let row = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Serializes tokens as newline-delimited JSON, one token per line.
+pub fn tokens_to_ndjson<'a, I: IntoIterator<Item = &'a Token>>(tokens: I) -> String {
+    tokens
+        .into_iter()
+        .map(|token| serde_json::to_string(token).expect("A token is always serializable") + "\n")
+        .collect()
+}
+
+/// Parses tokens serialized as newline-delimited JSON, skipping blank lines.
+///
+/// # Returns
+///
+/// A `Result` containing the tokens, or an `EnsoError::Deserialize` for the first
+/// invalid line.
+pub fn tokens_from_ndjson(ndjson: &str) -> Result<Vec<Token>> {
+    ndjson
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_json(line.as_bytes()))
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats a time as an ISO 8601 UTC timestamp, e.g. `2024-05-01T12:00:00Z`.
+fn iso8601(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // Converts days since the epoch to a civil date (Howard Hinnant's algorithm).
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+impl TokenRegistry {
+    /// Exports the tokens matching `filter` as a token list named `name`.
+    ///
+    /// Tokens without metadata are skipped, see [`TokenList::new`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the list, or an `EnsoError::InvalidTokenList` if the
+    /// name is invalid or no token can be exported.
+    pub fn export_token_list(&self, name: &str, filter: &TokenFilter) -> Result<TokenList> {
+        let list = TokenList::new(name, self.iter().filter(|token| filter.matches(token)));
+        list.validate()?;
+        Ok(list)
+    }
+
+    /// Adds or replaces the tokens of `list`.
+    ///
+    /// # Returns
+    ///
+    /// How many tokens were added, updated or already up to date.
+    pub fn import_token_list(&mut self, list: &TokenList) -> RegistryUpdate {
+        self.upsert(list.to_tokens())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{metadata::token_filter::TokenType, testing::MockServer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_list_round_trip() {
+        let server = MockServer::start();
+        let registry = server
+            .enso()
+            .token_registry(TokenFilter::new().include_metadata(true))
+            .await
+            .unwrap();
+
+        let filter = TokenFilter::new().chain_id(10).token_type(TokenType::Defi);
+        let list = registry
+            .export_token_list("Optimism DeFi", &filter)
+            .unwrap();
+        assert_eq!(list.tokens.len(), 3);
+        assert_eq!(list.version.major, 1);
+
+        let json = list.to_json();
+        assert!(json.contains("\"logoURI\""));
+        let parsed = TokenList::from_json(&json).unwrap();
+        assert_eq!(parsed, list);
+
+        let mut imported = TokenRegistry::new();
+        assert_eq!(imported.import_token_list(&parsed).added, 3);
+        for token in imported.iter() {
+            assert_eq!(Some(token), registry.get(token.chain_id, &token.address));
+        }
+
+        assert!(TokenList::from_json("{\"name\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_token_list_schema() {
+        let entry = |symbol: &str, name: &str| TokenListEntry {
+            chain_id: 10,
+            address: "0x0b2c639c533813f4aa9d7837caf62653d097ff85".to_string(),
+            decimals: 6,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            logo_uri: None,
+            extensions: BTreeMap::new(),
+        };
+        let list = |name: &str, tokens: Vec<TokenListEntry>| TokenList {
+            name: name.to_string(),
+            timestamp: "2024-05-01T12:00:00Z".to_string(),
+            version: TokenListVersion {
+                major: 1,
+                minor: 0,
+                patch: 0,
+            },
+            tokens,
+            keywords: Vec::new(),
+            logo_uri: None,
+        };
+        let is_invalid = |list: TokenList| {
+            matches!(list.validate(), Err(EnsoError::InvalidTokenList(_)))
+                && TokenList::from_json(&list.to_json()).is_err()
+        };
+
+        let valid = list("Optimism_DeFi 2", vec![entry("USDC", "USD Coin (Bridged)")]);
+        assert!(valid.validate().is_ok());
+        assert_eq!(TokenList::from_json(&valid.to_json()).unwrap(), valid);
+
+        assert!(is_invalid(list("", vec![entry("USDC", "USD Coin")])));
+        assert!(is_invalid(list(
+            &"a".repeat(31),
+            vec![entry("USDC", "USD Coin")]
+        )));
+        assert!(is_invalid(list("DeFi!", vec![entry("USDC", "USD Coin")])));
+        assert!(is_invalid(list("DeFi", Vec::new())));
+        assert!(is_invalid(list("DeFi", vec![entry("US DC", "USD Coin")])));
+        assert!(is_invalid(list(
+            "DeFi",
+            vec![entry(&"U".repeat(21), "USD Coin")]
+        )));
+        assert!(is_invalid(list(
+            "DeFi",
+            vec![entry("USDC", &"U".repeat(61))]
+        )));
+        assert!(is_invalid(list("DeFi", vec![entry("USDC", "USD\nCoin")])));
+        let mut token = entry("USDC", "USD Coin");
+        token.address = "0x0b2c".to_string();
+        assert!(is_invalid(list("DeFi", vec![token])));
+
+        let json = valid
+            .to_json()
+            .replace("\"decimals\": 6", "\"decimals\": 256");
+        assert!(matches!(
+            TokenList::from_json(&json),
+            Err(EnsoError::Deserialize { .. })
+        ));
+
+        let token = entry("US DC", "USD Coin").to_token();
+        assert!(TokenList::new("DeFi", [&token]).tokens.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_csv_and_ndjson() {
+        let server = MockServer::start();
+        let registry = server
+            .enso()
+            .token_registry(TokenFilter::new().chain_id(10).include_metadata(true))
+            .await
+            .unwrap();
+
+        let csv = tokens_to_csv(registry.iter());
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("chainId,address,type"));
+        let lp = lines
+            .iter()
+            .find(|line| line.contains("vAMMV2-USDC/WETH"))
+            .unwrap();
+        assert!(lp.contains(
+            "0x0b2c639c533813f4aa9d7837caf62653d097ff85;0x4200000000000000000000000000000000000006"
+        ));
+
+        let ndjson = tokens_to_ndjson(registry.iter());
+        let tokens = tokens_from_ndjson(&ndjson).unwrap();
+        assert_eq!(tokens, registry.iter().cloned().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(
+            iso8601(UNIX_EPOCH + Duration::from_secs(1_714_564_800)),
+            "2024-05-01T12:00:00Z"
+        );
+    }
+}
@@ -1,133 +1,135 @@
-use std::{pin::Pin, task::Poll};
+use std::pin::Pin;
 
-use anyhow::{anyhow, Result};
-use futures::{Future, Stream};
-use reqwest::{header::AUTHORIZATION, Client, Response};
-use serde::{Deserialize, Serialize};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::core::Enso;
+use crate::core::{Checkpoint, Endpoint, Enso, IntoQuery, Page, Paged, Paginated, Result};
 
-#[derive(Debug, Deserialize, Serialize)]
+use super::units::{format_units, parse_units};
+
+/// A token known by Enso.
+///
+/// The metadata fields are only filled when requested, e.g. with
+/// `TokenFilter::include_metadata`.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct Token {
-    chain_id: u32,
-    address: String,
+pub struct Token {
+    /// The ID of the chain the token lives on.
+    pub chain_id: u32,
+    /// The address of the token.
+    pub address: String,
+    /// The kind of token, e.g. `base` or `defi`.
     #[serde(rename = "type")]
-    kind: String,
-    protocol_slug: String,
-    underlying_tokens: Vec<String>,
-    primary_address: String,
+    pub kind: String,
+    /// The slug of the protocol the token belongs to.
+    pub protocol_slug: String,
+    /// The addresses of the tokens wrapped by this one.
+    pub underlying_tokens: Vec<String>,
+    /// The address of the contract to interact with, e.g. the vault of a
+    /// position token.
+    pub primary_address: String,
+    /// The symbol of the token, e.g. `USDC`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// The name of the token, e.g. `USD Coin`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The number of decimals of the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    /// URLs of the logos of the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logos_uri: Option<Vec<String>>,
+    /// The yearly yield of the token, in percent.
+    #[serde(
+        default,
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub apy: Option<f64>,
+    /// The total value locked in the token, in USD.
+    #[serde(
+        default,
+        deserialize_with = "number_or_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tvl: Option<f64>,
 }
 
-#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Meta {
-    total: u32,
-    last_page: u32,
-    current_page: u32,
-    per_page: u32,
-    prev: Option<u32>,
-    next: Option<u32>,
-}
+impl Token {
+    /// Returns the symbol of the token, or its address if the metadata is missing.
+    pub fn display_name(&self) -> &str {
+        self.symbol.as_deref().unwrap_or(&self.address)
+    }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Tokens {
-    meta: Meta,
-    data: Vec<Token>,
+    /// Formats a raw on-chain `amount` of this token using its decimals.
+    ///
+    /// # Returns
+    ///
+    /// The formatted amount, or `None` if the decimals are unknown or `amount`
+    /// isn't a non-negative integer.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::tokens::Token;
+    /// # fn example(usdc: Token) {
+    /// assert_eq!(usdc.format_amount("1500000").as_deref(), Some("1.5"));
+    /// # }
+    /// ```
+    pub fn format_amount(&self, amount: &str) -> Option<String> {
+        format_units(amount, self.decimals?)
+    }
+
+    /// Parses a decimal `amount` of this token into a raw on-chain amount.
+    ///
+    /// # Returns
+    ///
+    /// The raw amount, or `None` if the decimals are unknown or `amount` isn't a
+    /// valid amount of this token.
+    pub fn parse_amount(&self, amount: &str) -> Option<String> {
+        parse_units(amount, self.decimals?)
+    }
 }
 
-type ServerOutput = Result<Response, reqwest::Error>;
-type ParsingOutput = Result<Tokens, reqwest::Error>;
+fn number_or_string<'de, D>(des: D) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Number(f64),
+        String(String),
+    }
 
-enum StreamStates {
-    Checking,
-    PollingServer(Option<Pin<Box<dyn Future<Output = ServerOutput> + Send>>>),
-    PollingParsing(Option<Pin<Box<dyn Future<Output = ParsingOutput> + Send>>>),
+    Ok(match Option::<Number>::deserialize(des)? {
+        Some(Number::Number(number)) => Some(number),
+        Some(Number::String(number)) => number.parse().ok(),
+        None => None,
+    })
 }
 
-pub struct PaginatedTokensStream {
-    client: Client,
-    url: String,
-    auth: String,
-    params: Vec<(String, String)>,
-    page: u32,
-    total_pages: Option<u32>,
-    state: StreamStates,
-}
+pub use crate::core::Meta;
 
-impl Stream for PaginatedTokensStream {
-    type Item = Result<Vec<String>>;
-
-    fn poll_next(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        let this = self.get_mut();
-
-        if let StreamStates::Checking = this.state {
-            if this
-                .total_pages
-                .is_some_and(|total_pages| this.page >= total_pages)
-            {
-                return Poll::Ready(None);
-            }
-            let response = this
-                .client
-                .get(&this.url)
-                .header(AUTHORIZATION, this.auth.clone())
-                .query(&this.params)
-                .query(&[("page".to_string(), (this.page + 1).to_string())])
-                .send();
-            this.state = StreamStates::PollingServer(Some(Box::pin(response)))
-        }
+type Tokens = Paged<Token>;
 
-        if let StreamStates::PollingServer(server) = &mut this.state {
-            let Some(future) = server.as_mut() else {
-                return Poll::Ready(None);
-            };
-            let response = match futures::ready!(future.as_mut().poll(cx)) {
-                Ok(response) => response,
-                Err(_) => return Poll::Ready(Some(Err(anyhow!("Couldn't get tokens")))),
-            };
-            let tokens = response.json::<Tokens>();
-            this.state = StreamStates::PollingParsing(Some(Box::pin(tokens)))
-        }
+/// A page of tokens yielded by a `PaginatedTokensStream`.
+pub type TokenPage = Page<Token>;
 
-        if let StreamStates::PollingParsing(parsing) = &mut this.state {
-            let Some(future) = parsing.as_mut() else {
-                this.state = StreamStates::Checking;
-                return Poll::Ready(None);
-            };
-            match futures::ready!(future.as_mut().poll(cx)) {
-                Ok(tokens) => {
-                    this.page += 1;
-                    this.total_pages = Some(tokens.meta.last_page);
-                    this.state = StreamStates::Checking;
-                    return std::task::Poll::Ready(Some(Ok(tokens
-                        .data
-                        .iter()
-                        .map(|token| token.address.clone())
-                        .collect())));
-                }
-                Err(_) => {
-                    this.state = StreamStates::Checking;
-                    return std::task::Poll::Ready(Some(Err(anyhow!("Couldn't parse result"))));
-                }
-            }
-        };
+/// Progress of a `PaginatedTokensStream`, from which `Enso::resume_tokens_pages`
+/// can resume.
+pub type TokensCheckpoint = Checkpoint;
 
-        this.state = StreamStates::Checking;
-        Poll::Ready(None)
-    }
-}
+/// Stream of the pages of tokens matching a filter. See [`Paginated`].
+pub type PaginatedTokensStream = Paginated<Tokens>;
 
 impl Enso {
     /// Streams tokens based on provided parameters.
     ///
     /// # Arguments
     ///
-    /// * `params` - A slice of key-value pairs to filter the tokens.
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
     ///
     /// # Returns
     ///
@@ -135,36 +137,115 @@ impl Enso {
     ///
     /// # Example
     ///
-    /// ```
-    /// let stream = enso.tokens_stream(&[("param1", "value1")]);
+    /// ```no_run
+    /// # use enso::{core::Enso, metadata::token_filter::TokenFilter};
+    /// # fn example(enso: Enso) {
+    /// let stream = enso.tokens_stream(&TokenFilter::new().chain_id(1));
+    /// # }
     /// ```
     pub fn tokens_stream(
         &self,
-        params: &[(&str, &str)],
+        params: impl IntoQuery,
     ) -> Pin<Box<dyn Stream<Item = Result<Vec<String>>> + Send>> {
-        let client = Client::new();
-        let url = format!("{}/tokens", self.get_api_url());
-        let auth = format!("Bearer {}", self.api_key);
-        let stream = PaginatedTokensStream {
-            client,
-            url,
-            auth,
-            params: params
-                .iter()
-                .map(|(f, v)| (f.to_string(), v.to_string()))
-                .collect::<Vec<(String, String)>>(),
-            page: 0,
-            total_pages: None,
-            state: StreamStates::Checking,
-        };
-        Box::pin(stream)
+        Box::pin(self.tokens_pages(params).map(|page| {
+            page.map(|page| page.items.into_iter().map(|token| token.address).collect())
+        }))
+    }
+
+    /// Streams the full tokens matching the provided parameters, one page at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
+    ///
+    /// # Returns
+    ///
+    /// A pinned `Stream` yielding results with vectors of `Token`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::token_filter::TokenFilter;
+    /// # use futures::StreamExt;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let mut stream = enso.token_details_stream(TokenFilter::new().chain_id(1));
+    /// while let Some(tokens) = stream.next().await {
+    ///     for token in tokens? {
+    ///         println!("{} ({})", token.address, token.protocol_slug);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn token_details_stream(
+        &self,
+        params: impl IntoQuery,
+    ) -> Pin<Box<dyn Stream<Item = Result<Vec<Token>>> + Send>> {
+        Box::pin(
+            self.tokens_pages(params)
+                .map(|page| page.map(|page| page.items)),
+        )
+    }
+
+    /// Creates a stream of the pages of tokens matching the provided parameters,
+    /// whose concurrency and ordering can be configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
+    ///
+    /// # Returns
+    ///
+    /// A `PaginatedTokensStream` yielding results with a `TokenPage`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::{core::Enso, metadata::token_filter::TokenFilter};
+    /// # fn example(enso: Enso) {
+    /// let pages = enso
+    ///     .tokens_pages(TokenFilter::new().chain_id(1))
+    ///     .concurrency(8)
+    ///     .unordered();
+    /// # }
+    /// ```
+    pub fn tokens_pages(&self, params: impl IntoQuery) -> PaginatedTokensStream {
+        Paginated::new(
+            self.clone(),
+            Endpoint::Tokens,
+            Checkpoint::new(params.into_query()),
+        )
+    }
+
+    /// Creates a stream of the pages of tokens resuming from `checkpoint`.
+    ///
+    /// # Arguments
+    ///
+    /// * `checkpoint` - The progress saved from a previous stream.
+    ///
+    /// # Returns
+    ///
+    /// A `PaginatedTokensStream` yielding the pages not covered by the checkpoint.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::{core::Enso, metadata::tokens::TokensCheckpoint};
+    /// # fn example(enso: Enso, saved: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// let checkpoint: TokensCheckpoint = serde_json::from_str(&saved)?;
+    /// let pages = enso.resume_tokens_pages(checkpoint).concurrency(4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume_tokens_pages(&self, checkpoint: TokensCheckpoint) -> PaginatedTokensStream {
+        Paginated::new(self.clone(), Endpoint::Tokens, checkpoint)
     }
 
     /// Retrieves a list of tokens based on provided parameters.
     ///
     /// # Arguments
     ///
-    /// * `params` - A slice of key-value pairs to filter the tokens.
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
     ///
     /// # Returns
     ///
@@ -172,51 +253,69 @@ impl Enso {
     ///
     /// # Example
     ///
+    /// ```no_run
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let (meta, tokens) = enso.get_tokens(&[("chainId", "1"), ("page", "2")]).await?;
+    /// # Ok(())
+    /// # }
     /// ```
-    /// let (meta, tokens) = enso.get_tokens(&[("param1", "value1")]).await?;
+    pub async fn get_tokens(&self, params: impl IntoQuery) -> Result<(Meta, Vec<String>)> {
+        let (meta, tokens) = self.get_token_details(params).await?;
+        Ok((
+            meta,
+            tokens.into_iter().map(|token| token.address).collect(),
+        ))
+    }
+
+    /// Retrieves a page of full tokens based on provided parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - A `TokenFilter`, or a slice of key-value pairs, to filter the tokens.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing metadata and a vector of `Token`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::token_filter::TokenFilter;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let (meta, tokens) = enso.get_token_details(TokenFilter::new().chain_id(1)).await?;
+    /// # Ok(())
+    /// # }
     /// ```
-    pub async fn get_tokens(&self, params: &[(&str, &str)]) -> Result<(Meta, Vec<String>)> {
-        let client = Client::new();
-        let url = format!("{}/tokens", self.get_api_url());
-        let auth = format!("Bearer {}", self.api_key);
-        let response = client
-            .get(&url)
-            .header(AUTHORIZATION, auth)
-            .query(params)
-            .send()
-            .await;
-        response
-            .map_err(|_| anyhow!("Couldn't get tokens"))?
-            .json::<Tokens>()
+    #[tracing::instrument(skip_all)]
+    pub async fn get_token_details(&self, params: impl IntoQuery) -> Result<(Meta, Vec<Token>)> {
+        let params = params.into_query();
+        let path = Endpoint::Tokens.path();
+        self.get_json::<Tokens>(path, &params)
             .await
-            .map_err(|_| anyhow!("Couldn't parse result"))
-            .map(|tokens| {
-                (
-                    tokens.meta,
-                    tokens
-                        .data
-                        .iter()
-                        .map(|token| token.address.clone())
-                        .collect(),
-                )
-            })
+            .map(|tokens| (tokens.meta, tokens.data))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use futures::StreamExt;
+    use reqwest::Method;
+    use serde_json::json;
 
-    use crate::core::Version;
+    use crate::{
+        core::{Enso, EnsoError, HttpResponse, InMemoryTransport},
+        metadata::token_filter::{TokenFilter, TokenType},
+        testing::{page_response, MockServer},
+    };
 
-    use super::*;
+    use super::{TokenPage, TokensCheckpoint};
 
     #[tokio::test]
     async fn test_get_tokens() {
-        let enso = Enso::new(
-            "1e02632d-6feb-4a75-a157-documentation".to_string(),
-            Version::V1,
-        );
+        let server = MockServer::start();
+        let enso = server.enso();
         let filter = vec![("chainId", "10")];
         let mut page = 1;
         let mut total = 0u32;
@@ -242,10 +341,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_tokens_stream() {
-        let enso = Enso::new(
-            "1e02632d-6feb-4a75-a157-documentation".to_string(),
-            Version::V1,
-        );
+        let server = MockServer::start();
+        let enso = server.enso();
         let tokens = enso.get_tokens(&[("chainId", "10")]).await;
         let Ok((meta, _)) = tokens else {
             panic!("retrieving tokens failed!");
@@ -261,4 +358,148 @@ mod tests {
         }
         assert_eq!(total, meta.total);
     }
+
+    #[tokio::test]
+    async fn test_concurrent_pages() {
+        let server = MockServer::start();
+        let enso = server.enso();
+        let filter = TokenFilter::new().chain_id(10);
+        let collect = |pages: Vec<crate::core::Result<TokenPage>>| {
+            pages
+                .into_iter()
+                .flat_map(|page| page.unwrap().items)
+                .collect::<Vec<_>>()
+        };
+
+        let sequential = collect(enso.tokens_pages(&filter).collect().await);
+        let ordered = collect(enso.tokens_pages(&filter).concurrency(3).collect().await);
+        let mut unordered = collect(
+            enso.tokens_pages(&filter)
+                .concurrency(3)
+                .unordered()
+                .collect()
+                .await,
+        );
+
+        assert_eq!(sequential.len(), 7);
+        assert_eq!(ordered, sequential);
+        unordered.sort_by(|a, b| a.address.cmp(&b.address));
+        let mut sorted = sequential.clone();
+        sorted.sort_by(|a, b| a.address.cmp(&b.address));
+        assert_eq!(unordered, sorted);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let server = MockServer::start();
+        let enso = server.enso();
+        let mut pages = enso.tokens_pages(TokenFilter::new().chain_id(10));
+
+        let first = pages.next().await.unwrap().unwrap();
+        assert_eq!((first.page, first.total_pages), (1, 4));
+        let checkpoint = serde_json::to_string(&pages.checkpoint()).unwrap();
+        drop(pages);
+
+        let checkpoint = serde_json::from_str::<TokensCheckpoint>(&checkpoint).unwrap();
+        let mut resumed = enso
+            .resume_tokens_pages(checkpoint)
+            .concurrency(2)
+            .unordered()
+            .map(|page| page.unwrap().page)
+            .collect::<Vec<_>>()
+            .await;
+        resumed.sort();
+        assert_eq!(resumed, vec![2, 3, 4]);
+
+        let started = enso
+            .tokens_pages(TokenFilter::new().chain_id(10))
+            .start_page(4)
+            .map(|page| page.unwrap().page)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(started, vec![4]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_page_ends_stream() {
+        let page = |page| page_response(page, 3, 1, Vec::new());
+        let transport = Arc::new(
+            InMemoryTransport::new()
+                .on(Method::GET, "/api/v1/tokens", page(1))
+                .on_query(
+                    Method::GET,
+                    "/api/v1/tokens",
+                    &[("page", "2")],
+                    HttpResponse::json(401, &json!({ "message": "Invalid API key" })),
+                )
+                .on_query(Method::GET, "/api/v1/tokens", &[("page", "3")], page(3)),
+        );
+        let enso = Enso::builder()
+            .api_key("key")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let mut pages = enso.tokens_pages(TokenFilter::new());
+        let results = pages.by_ref().collect::<Vec<_>>().await;
+
+        assert!(matches!(
+            results.as_slice(),
+            [Ok(_), Err(EnsoError::Unauthorized { status: 401, .. })]
+        ));
+        assert_eq!(pages.checkpoint().next_page, 2);
+        assert!(pages.next().await.is_none());
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_token_metadata() {
+        let server = MockServer::start();
+        let enso = server.enso();
+        let filter = TokenFilter::new()
+            .address("0x38d693ce1df5aadf7bc62595a37d667ad57922e5")
+            .include_metadata(true);
+
+        let (_, tokens) = enso.get_token_details(&filter).await.unwrap();
+        let token = &tokens[0];
+        assert_eq!(token.display_name(), "aOptUSDCn");
+        assert_eq!(token.decimals, Some(6));
+        assert!(token.apy.is_some() && token.tvl.is_some());
+        assert_eq!(token.format_amount("2500000").as_deref(), Some("2.5"));
+        assert_eq!(token.parse_amount("2.5").as_deref(), Some("2500000"));
+
+        let (_, tokens) = enso
+            .get_token_details(filter.include_metadata(false))
+            .await
+            .unwrap();
+        assert_eq!(tokens[0].symbol, None);
+        assert_eq!(tokens[0].display_name(), tokens[0].address);
+        assert_eq!(tokens[0].format_amount("1"), None);
+    }
+
+    #[tokio::test]
+    async fn test_token_details() {
+        let server = MockServer::start();
+        let enso = server.enso();
+
+        let filter = TokenFilter::new().chain_id(10).token_type(TokenType::Defi);
+        let (meta, tokens) = enso.get_token_details(&filter).await.unwrap();
+        assert_eq!(meta.current_page, 1);
+        assert!(!tokens.is_empty());
+        assert!(tokens
+            .iter()
+            .all(|token| token.chain_id == 10 && token.kind == "defi"));
+
+        let tokens = enso
+            .token_details_stream(&[("chainId", "10")])
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::core::Result<Vec<_>>>()
+            .unwrap()
+            .concat();
+        assert!(tokens
+            .iter()
+            .any(|token| !token.underlying_tokens.is_empty()));
+    }
 }
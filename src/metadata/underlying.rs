@@ -0,0 +1,370 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+};
+
+use crate::core::{Enso, Result};
+
+use super::{registry::TokenRegistry, token_filter::TokenFilter, tokens::Token};
+
+/// Tree of the tokens wrapped by a token, down to the base tokens.
+///
+/// Every node is a token address. A node is a leaf when the token doesn't wrap
+/// anything, isn't known, or already appears among its ancestors, which would
+/// otherwise make the tree infinite.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::metadata::registry::TokenRegistry;
+/// # fn example(registry: TokenRegistry) {
+/// if let Some(tree) = registry.underlying_tree(10, "0x0493bf8b6dbb159ce2db2e0e8403e753abd1235b") {
+///     println!("{}", tree);
+/// }
+/// // vAMMV2-USDC/WETH 0x0493bf8b6dbb159ce2db2e0e8403e753abd1235b (velodrome-v2)
+/// // ├── USDC 0x0b2c639c533813f4aa9d7837caf62653d097ff85 (usdc)
+/// // └── WETH 0x4200000000000000000000000000000000000006 (weth)
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnderlyingTree {
+    /// The ID of the chain of the token.
+    pub chain_id: u32,
+    /// The address of the token.
+    pub address: String,
+    /// The token, if known.
+    pub token: Option<Token>,
+    /// Whether the token already appears among the ancestors of this node.
+    pub cycle: bool,
+    /// The trees of the tokens wrapped by this one.
+    pub children: Vec<UnderlyingTree>,
+}
+
+impl UnderlyingTree {
+    /// Returns `true` if the node is a known token that doesn't wrap anything.
+    pub fn is_base(&self) -> bool {
+        self.token.is_some() && !self.cycle && self.children.is_empty()
+    }
+
+    /// Returns `true` if a cycle was found anywhere in the tree.
+    pub fn has_cycle(&self) -> bool {
+        self.cycle || self.children.iter().any(UnderlyingTree::has_cycle)
+    }
+
+    /// Returns the base tokens of the tree, each one once, in the order they are
+    /// first found.
+    pub fn base_tokens(&self) -> Vec<&Token> {
+        let mut seen = HashSet::new();
+        let mut tokens = Vec::new();
+        self.walk(&mut |node| {
+            if let (true, Some(token)) = (node.is_base(), &node.token) {
+                if seen.insert(node.address.to_lowercase()) {
+                    tokens.push(token);
+                }
+            }
+        });
+        tokens
+    }
+
+    /// Returns the addresses of the tree that aren't known, each one once.
+    pub fn unresolved(&self) -> BTreeSet<String> {
+        let mut addresses = BTreeSet::new();
+        self.walk(&mut |node| {
+            if node.token.is_none() {
+                addresses.insert(node.address.to_lowercase());
+            }
+        });
+        addresses
+    }
+
+    /// Returns the depth of the tree, a token without underlying tokens being 1.
+    pub fn depth(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(UnderlyingTree::depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a UnderlyingTree)) {
+        visit(self);
+        for child in &self.children {
+            child.walk(visit);
+        }
+    }
+
+    fn label(&self) -> String {
+        let label = match &self.token {
+            Some(token) => format!(
+                "{} {} ({})",
+                token.display_name(),
+                self.address,
+                token.protocol_slug
+            ),
+            None => format!("{} (unknown)", self.address),
+        };
+        if self.cycle {
+            format!("{} [cycle]", label)
+        } else {
+            label
+        }
+    }
+
+    fn render(&self, prefix: &str, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i + 1 == self.children.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            writeln!(f, "{}{}{}", prefix, branch, child.label())?;
+            child.render(&format!("{}{}", prefix, indent), f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for UnderlyingTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.label())?;
+        self.render("", f)
+    }
+}
+
+impl TokenRegistry {
+    /// Resolves the tree of the tokens wrapped by the token at `address` on the
+    /// chain `chain_id`, using the tokens of the registry only.
+    ///
+    /// # Returns
+    ///
+    /// The tree, or `None` if the token isn't in the registry. Underlying tokens
+    /// missing from the registry are unknown leaves of the tree.
+    pub fn underlying_tree(&self, chain_id: u32, address: &str) -> Option<UnderlyingTree> {
+        self.get(chain_id, address)?;
+        Some(self.build_tree(chain_id, address, &mut Vec::new()))
+    }
+
+    fn build_tree(&self, chain_id: u32, address: &str, path: &mut Vec<String>) -> UnderlyingTree {
+        let key = address.to_lowercase();
+        let token = self.get(chain_id, address).cloned();
+        if path.contains(&key) {
+            return UnderlyingTree {
+                chain_id,
+                address: address.to_string(),
+                token,
+                cycle: true,
+                children: Vec::new(),
+            };
+        }
+
+        path.push(key);
+        let children = token
+            .iter()
+            .flat_map(|token| &token.underlying_tokens)
+            .map(|underlying| self.build_tree(chain_id, underlying, path))
+            .collect();
+        path.pop();
+        UnderlyingTree {
+            chain_id,
+            address: address.to_string(),
+            token,
+            cycle: false,
+            children,
+        }
+    }
+}
+
+impl Enso {
+    /// Resolves the tree of the tokens wrapped by the token at `address` on the
+    /// chain `chain_id`, fetching the tokens missing from `registry`.
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The known tokens, completed with the fetched ones.
+    /// * `chain_id` - The ID of the chain of the token.
+    /// * `address` - The address of the token.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the tree. Tokens the API doesn't know are unknown
+    /// leaves of the tree.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::metadata::registry::TokenRegistry;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let mut registry = TokenRegistry::new();
+    /// let tree = enso
+    ///     .resolve_underlying(&mut registry, 1, "0xb4e16d0168e52d35cacd2c6185b44281ec28c9dc")
+    ///     .await?;
+    /// let bases = tree.base_tokens();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn resolve_underlying(
+        &self,
+        registry: &mut TokenRegistry,
+        chain_id: u32,
+        address: &str,
+    ) -> Result<UnderlyingTree> {
+        let mut missing = HashSet::new();
+        loop {
+            let tree = registry
+                .underlying_tree(chain_id, address)
+                .unwrap_or_else(|| UnderlyingTree {
+                    chain_id,
+                    address: address.to_string(),
+                    token: None,
+                    cycle: false,
+                    children: Vec::new(),
+                });
+            let unresolved = tree
+                .unresolved()
+                .into_iter()
+                .filter(|address| !missing.contains(address))
+                .collect::<Vec<_>>();
+            if unresolved.is_empty() {
+                return Ok(tree);
+            }
+            for address in unresolved {
+                let filter = TokenFilter::new()
+                    .chain_id(chain_id)
+                    .address(&address)
+                    .include_metadata(true);
+                let (_, tokens) = self.get_token_details(&filter).await?;
+                registry.upsert(tokens);
+                // The API may answer with other tokens, so only a token now in the
+                // registry counts as resolved.
+                if registry.get(chain_id, &address).is_none() {
+                    missing.insert(address);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use reqwest::Method;
+
+    use crate::{
+        core::InMemoryTransport,
+        testing::{page_response, MockServer},
+    };
+
+    use super::*;
+
+    fn token(address: &str, symbol: &str, underlying: &[&str]) -> Token {
+        Token {
+            chain_id: 1,
+            address: address.to_string(),
+            kind: if underlying.is_empty() {
+                "base"
+            } else {
+                "defi"
+            }
+            .to_string(),
+            protocol_slug: "test".to_string(),
+            underlying_tokens: underlying.iter().map(|a| a.to_string()).collect(),
+            primary_address: address.to_string(),
+            symbol: Some(symbol.to_string()),
+            name: None,
+            decimals: Some(18),
+            logos_uri: None,
+            apy: None,
+            tvl: None,
+        }
+    }
+
+    #[test]
+    fn test_tree_and_cycles() {
+        let registry = TokenRegistry::from_iter([
+            token("0xa", "A", &[]),
+            token("0xb", "B", &[]),
+            token("0xlp", "LP", &["0xa", "0xb"]),
+            token("0xvault", "VAULT", &["0xlp", "0xa", "0xmissing"]),
+            token("0xx", "X", &["0xy"]),
+            token("0xy", "Y", &["0xx"]),
+        ]);
+
+        let tree = registry.underlying_tree(1, "0xVAULT").unwrap();
+        assert_eq!(tree.depth(), 3);
+        assert!(!tree.has_cycle());
+        let bases = tree
+            .base_tokens()
+            .into_iter()
+            .map(|token| token.address.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bases, vec!["0xa", "0xb"]);
+        assert_eq!(tree.unresolved(), BTreeSet::from(["0xmissing".to_string()]));
+        assert_eq!(
+            tree.to_string(),
+            "VAULT 0xVAULT (test)\n\
+             ├── LP 0xlp (test)\n\
+             │   ├── A 0xa (test)\n\
+             │   └── B 0xb (test)\n\
+             ├── A 0xa (test)\n\
+             └── 0xmissing (unknown)\n"
+        );
+
+        let cycle = registry.underlying_tree(1, "0xx").unwrap();
+        assert!(cycle.has_cycle());
+        assert!(cycle.base_tokens().is_empty());
+        assert!(registry.underlying_tree(1, "0xmissing").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_underlying() {
+        let server = MockServer::start();
+        let enso = server.enso();
+        let mut registry = TokenRegistry::new();
+
+        let tree = enso
+            .resolve_underlying(
+                &mut registry,
+                10,
+                "0x0493bf8b6dbb159ce2db2e0e8403e753abd1235b",
+            )
+            .await
+            .unwrap();
+
+        let bases = tree
+            .base_tokens()
+            .into_iter()
+            .map(|token| token.display_name())
+            .collect::<Vec<_>>();
+        assert_eq!(bases, vec!["USDC", "WETH"]);
+        assert_eq!(registry.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_underlying_unrelated_tokens() {
+        // The address filter is ignored and the same unrelated token is returned.
+        let other = serde_json::to_value(token("0xother", "OTHER", &[])).unwrap();
+        let transport = Arc::new(InMemoryTransport::new().on(
+            Method::GET,
+            "/api/v1/tokens",
+            page_response(1, 1, 1, vec![other]),
+        ));
+        let enso = Enso::builder()
+            .api_key("key")
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+        let mut registry = TokenRegistry::from_iter([token("0xlp", "LP", &["0xa"])]);
+
+        let tree = enso
+            .resolve_underlying(&mut registry, 1, "0xlp")
+            .await
+            .unwrap();
+
+        assert_eq!(tree.unresolved(), BTreeSet::from(["0xa".to_string()]));
+        assert_eq!(transport.requests().len(), 1);
+        assert!(registry.get(1, "0xother").is_some());
+    }
+}
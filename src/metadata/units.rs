@@ -0,0 +1,99 @@
+//! Conversions between raw on-chain amounts and human-readable decimal amounts.
+//!
+//! Amounts are handled as strings of digits so values as large as a `uint256`
+//! don't lose precision.
+
+/// Formats a raw on-chain `amount` as a decimal number with `decimals` decimals.
+///
+/// Trailing zeros of the fractional part are removed.
+///
+/// # Returns
+///
+/// The formatted amount, or `None` if `amount` isn't a non-negative integer.
+///
+/// # Example
+///
+/// ```
+/// # use enso::metadata::units::format_units;
+/// assert_eq!(format_units("1500000", 6).as_deref(), Some("1.5"));
+/// ```
+pub fn format_units(amount: &str, decimals: u8) -> Option<String> {
+    let amount = amount.trim();
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let decimals = decimals as usize;
+    let digits = amount.trim_start_matches('0');
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    Some(if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    })
+}
+
+/// Parses a decimal `amount` into a raw on-chain amount with `decimals` decimals.
+///
+/// # Returns
+///
+/// The raw amount, or `None` if `amount` isn't a non-negative decimal number or
+/// has more fractional digits than `decimals`.
+///
+/// # Example
+///
+/// ```
+/// # use enso::metadata::units::parse_units;
+/// assert_eq!(parse_units("1.5", 6).as_deref(), Some("1500000"));
+/// ```
+pub fn parse_units(amount: &str, decimals: u8) -> Option<String> {
+    let amount = amount.trim();
+    let (integer, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (integer.is_empty() && fraction.is_empty()) || !is_digits(integer) || !is_digits(fraction) {
+        return None;
+    }
+    let fraction = fraction.trim_end_matches('0');
+    let decimals = decimals as usize;
+    if fraction.len() > decimals {
+        return None;
+    }
+    let raw = format!("{}{:0<width$}", integer, fraction, width = decimals);
+    let raw = raw.trim_start_matches('0');
+    Some(if raw.is_empty() { "0" } else { raw }.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_units() {
+        assert_eq!(format_units("1500000", 6).as_deref(), Some("1.5"));
+        assert_eq!(format_units("42", 6).as_deref(), Some("0.000042"));
+        assert_eq!(format_units("1000", 0).as_deref(), Some("1000"));
+        assert_eq!(format_units("0", 18).as_deref(), Some("0"));
+        assert_eq!(
+            format_units(
+                "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+                18
+            )
+            .as_deref(),
+            Some("115792089237316195423570985008687907853269984665640564039457.584007913129639935")
+        );
+        assert_eq!(format_units("-1", 6), None);
+        assert_eq!(format_units("1.5", 6), None);
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!(parse_units("1.5", 6).as_deref(), Some("1500000"));
+        assert_eq!(parse_units(".000042", 6).as_deref(), Some("42"));
+        assert_eq!(parse_units("0", 18).as_deref(), Some("0"));
+        assert_eq!(parse_units("12.50", 1).as_deref(), Some("125"));
+        assert_eq!(parse_units("1.0000001", 6), None);
+        assert_eq!(parse_units("1,5", 6), None);
+        assert_eq!(parse_units(".", 6), None);
+    }
+}
@@ -0,0 +1,438 @@
+//! Offline mock of the Enso API for tests.
+//!
+//! [`MockServer`] spins up a local HTTP server answering the endpoints of the API
+//! with realistic fixtures, so code depending on `Enso` can be tested without the
+//! network. Enable the `testing` feature to use it outside of this crate.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use enso::testing::MockServer;
+//! # async fn example() -> enso::core::Result<()> {
+//! let server = MockServer::start();
+//! let enso = server.enso();
+//! let networks = enso.get_networks().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+
+use reqwest::{Method, Url};
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::core::{Enso, HttpRequest, HttpResponse};
+
+/// API key accepted by the mock server.
+pub const API_KEY: &str = "enso-testing-key";
+
+/// Default number of tokens returned per page by `/tokens`.
+pub const PAGE_SIZE: usize = 2;
+
+static NETWORKS: &str = include_str!("fixtures/networks.json");
+static PROTOCOLS: &str = include_str!("fixtures/protocols.json");
+static ACTIONS: &str = include_str!("fixtures/actions.json");
+static PROTOCOL_ACTIONS: &str = include_str!("fixtures/protocol_actions.json");
+static TOKENS: &str = include_str!("fixtures/tokens.json");
+static BUNDLE: &str = include_str!("fixtures/bundle.json");
+static PRICES: &str = include_str!("fixtures/prices.json");
+static BALANCES: &str = include_str!("fixtures/balances.json");
+
+/// Local HTTP server mimicking the Enso API.
+///
+/// The server serves `/networks`, `/protocols`, `/actions` (global and per protocol),
+/// the paginated `/tokens`, `/prices`, `/wallet/balances` and `/shortcuts/bundle`
+/// under `/api/v1`, and rejects requests without the [`API_KEY`] bearer token. It
+/// stops when dropped.
+pub struct MockServer {
+    url: String,
+    server: Arc<Server>,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on a random local port, returning [`PAGE_SIZE`] tokens per page.
+    pub fn start() -> MockServer {
+        MockServer::with_page_size(PAGE_SIZE)
+    }
+
+    /// Starts a server on a random local port, returning `page_size` tokens per page.
+    pub fn with_page_size(page_size: usize) -> MockServer {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("Couldn't start mock server"));
+        let address = server
+            .server_addr()
+            .to_ip()
+            .expect("Mock server isn't listening on an IP address");
+        let url = format!("http://{}", address);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let handle = {
+            let server = server.clone();
+            let requests = requests.clone();
+            let url = url.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    handle_request(request, &url, page_size, &requests);
+                }
+            })
+        };
+
+        MockServer {
+            url,
+            server,
+            requests,
+            handle: Some(handle),
+        }
+    }
+
+    /// Returns the base URL of the server, e.g. `http://127.0.0.1:38211`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Creates an `Enso` client pointing at this server.
+    pub fn enso(&self) -> Enso {
+        Enso::builder()
+            .api_key(API_KEY)
+            .base_url(&self.url)
+            .build()
+            .expect("Couldn't create the Enso client")
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            _ = handle.join();
+        }
+    }
+}
+
+fn handle_request(
+    mut request: Request,
+    base_url: &str,
+    page_size: usize,
+    requests: &Mutex<Vec<HttpRequest>>,
+) {
+    let mut body = Vec::new();
+    _ = request.as_reader().read_to_end(&mut body);
+    let url = format!("{}{}", base_url, request.url());
+    let query = Url::parse(&url)
+        .map(|url| url.query_pairs().into_owned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let recorded = HttpRequest {
+        method: Method::from_bytes(request.method().as_str().as_bytes()).unwrap_or(Method::GET),
+        url,
+        query,
+        headers: request
+            .headers()
+            .iter()
+            .map(|h| (h.field.to_string(), h.value.to_string()))
+            .collect(),
+        body: (!body.is_empty()).then_some(body),
+    };
+
+    let (status, response) = route(&recorded, page_size);
+    requests.lock().unwrap().push(recorded);
+
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(response.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    _ = request.respond(response);
+}
+
+fn route(request: &HttpRequest, page_size: usize) -> (u16, Value) {
+    let authorized = request.headers.iter().any(|(f, v)| {
+        f.eq_ignore_ascii_case("authorization") && *v == format!("Bearer {}", API_KEY)
+    });
+    if !authorized {
+        return error(401, "Invalid API key");
+    }
+
+    let path = request.path();
+    match (request.method.as_str(), path.as_str()) {
+        ("GET", "/api/v1/networks") => (200, fixture(NETWORKS)),
+        ("GET", "/api/v1/protocols") => protocols(request),
+        ("GET", "/api/v1/actions") => (200, fixture(ACTIONS)),
+        ("GET", path) if path.starts_with("/api/v1/actions/") => {
+            let slug = path.trim_start_matches("/api/v1/actions/");
+            match fixture(PROTOCOL_ACTIONS).get(slug) {
+                Some(actions) => (200, actions.clone()),
+                None => error(404, &format!("Protocol {} not found", slug)),
+            }
+        }
+        ("GET", "/api/v1/tokens") => tokens(request, page_size),
+        ("GET", "/api/v1/wallet/balances") => balances(request),
+        ("POST", "/api/v1/shortcuts/bundle") => bundle(request),
+        ("GET", path) if path.starts_with("/api/v1/prices/") => prices(request, path),
+        _ => error(404, &format!("Cannot {} {}", request.method, path)),
+    }
+}
+
+fn protocols(request: &HttpRequest) -> (u16, Value) {
+    let Value::Array(protocols) = fixture(PROTOCOLS) else {
+        unreachable!("protocols fixture must be an array");
+    };
+    let chain_id = match request.query_param("chainId").map(str::parse::<u64>) {
+        None => None,
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => return error(400, "chainId must be a number"),
+    };
+    let protocols = protocols
+        .into_iter()
+        .filter(|protocol| {
+            chain_id.is_none_or(|id| {
+                protocol["chains"]
+                    .as_array()
+                    .is_some_and(|chains| chains.iter().any(|c| c["id"].as_u64() == Some(id)))
+            })
+        })
+        .collect();
+    (200, Value::Array(protocols))
+}
+
+fn tokens(request: &HttpRequest, page_size: usize) -> (u16, Value) {
+    let page = match request.query_param("page").map(str::parse::<usize>) {
+        None => 1,
+        Some(Ok(page)) if page > 0 => page,
+        Some(_) => return error(400, "page must be a positive number"),
+    };
+    let Value::Array(tokens) = fixture(TOKENS) else {
+        unreachable!("tokens fixture must be an array");
+    };
+    let filters = [
+        "chainId",
+        "protocolSlug",
+        "type",
+        "address",
+        "primaryAddress",
+    ];
+    let page_size = match request.query_param("pageSize").map(str::parse::<usize>) {
+        None => page_size,
+        Some(Ok(size)) if size > 0 => size,
+        Some(_) => return error(400, "pageSize must be a positive number"),
+    };
+    let tokens = tokens
+        .into_iter()
+        .filter(|token| {
+            request
+                .query_param("underlyingTokens")
+                .is_none_or(|address| {
+                    token["underlyingTokens"]
+                        .as_array()
+                        .is_some_and(|tokens| tokens.iter().any(|t| *t == address))
+                })
+        })
+        .filter(|token| {
+            filters.iter().all(|field| {
+                request
+                    .query_param(field)
+                    .is_none_or(|value| match &token[field] {
+                        Value::String(s) => s.eq_ignore_ascii_case(value),
+                        Value::Number(n) => value.parse::<u64>().ok() == n.as_u64(),
+                        _ => false,
+                    })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let include_metadata = request.query_param("includeMetadata") == Some("true");
+    let tokens = tokens
+        .into_iter()
+        .map(|mut token| {
+            if let (false, Some(fields)) = (include_metadata, token.as_object_mut()) {
+                for field in ["symbol", "name", "decimals", "logosUri", "apy", "tvl"] {
+                    fields.remove(field);
+                }
+            }
+            token
+        })
+        .collect::<Vec<_>>();
+
+    let total = tokens.len();
+    let data = tokens
+        .into_iter()
+        .skip((page - 1) * page_size)
+        .take(page_size)
+        .collect::<Vec<_>>();
+    (
+        200,
+        json!({ "meta": page_meta(page, total, page_size), "data": data }),
+    )
+}
+
+/// Returns the `meta` block of the page `page` of a paged endpoint with `total`
+/// items split in pages of `per_page`.
+pub fn page_meta(page: usize, total: usize, per_page: usize) -> Value {
+    let last_page = total.div_ceil(per_page).max(1);
+    json!({
+        "total": total,
+        "lastPage": last_page,
+        "currentPage": page,
+        "perPage": per_page,
+        "prev": (page > 1).then(|| page - 1),
+        "next": (page < last_page).then(|| page + 1),
+    })
+}
+
+/// Returns a `200` response of a paged endpoint with the page `page` of `total`
+/// items split in pages of `per_page`, e.g. to register on an `InMemoryTransport`.
+pub fn page_response(page: usize, total: usize, per_page: usize, data: Vec<Value>) -> HttpResponse {
+    HttpResponse::json(
+        200,
+        &json!({ "meta": page_meta(page, total, per_page), "data": data }),
+    )
+}
+
+fn balances(request: &HttpRequest) -> (u16, Value) {
+    let (Some(chain_id), Some(_)) = (
+        request.query_param("chainId"),
+        request.query_param("eoaAddress"),
+    ) else {
+        return error(400, "chainId and eoaAddress are required");
+    };
+    let Value::Array(balances) = fixture(BALANCES) else {
+        unreachable!("balances fixture must be an array");
+    };
+    let balances = balances
+        .into_iter()
+        .filter(|balance| balance["chainId"].as_u64() == chain_id.parse().ok())
+        .collect();
+    (200, Value::Array(balances))
+}
+
+fn bundle(request: &HttpRequest) -> (u16, Value) {
+    let (Some(_), Some(from_address)) = (
+        request.query_param("chainId"),
+        request.query_param("fromAddress"),
+    ) else {
+        return error(400, "chainId and fromAddress are required");
+    };
+    let actions = request
+        .body
+        .as_deref()
+        .and_then(|body| serde_json::from_slice::<Value>(body).ok());
+    let Some(Value::Array(actions)) = actions else {
+        return error(400, "The bundle must be an array of actions");
+    };
+
+    let mut response = fixture(BUNDLE);
+    response["bundle"] = Value::Array(actions);
+    response["tx"]["from"] = Value::String(from_address.to_string());
+    (201, response)
+}
+
+fn prices(request: &HttpRequest, path: &str) -> (u16, Value) {
+    let Value::Array(prices) = fixture(PRICES) else {
+        unreachable!("prices fixture must be an array");
+    };
+    let segments = path
+        .trim_start_matches("/api/v1/prices/")
+        .split('/')
+        .collect::<Vec<_>>();
+    let Ok(chain_id) = segments[0].parse::<u64>() else {
+        return error(400, "chainId must be a number");
+    };
+    let find = |address: &str| {
+        prices.iter().find(|price| {
+            price["chainId"].as_u64() == Some(chain_id)
+                && price["address"]
+                    .as_str()
+                    .is_some_and(|a| a.eq_ignore_ascii_case(address))
+        })
+    };
+
+    match segments[1..] {
+        [address] => match find(address) {
+            Some(price) => (200, price.clone()),
+            None => error(400, &format!("No price found for {}", address)),
+        },
+        [] => (
+            200,
+            Value::Array(
+                request
+                    .query
+                    .iter()
+                    .filter(|(name, _)| name == "addresses")
+                    .filter_map(|(_, address)| find(address).cloned())
+                    .collect(),
+            ),
+        ),
+        _ => error(404, &format!("Cannot GET {}", path)),
+    }
+}
+
+fn fixture(content: &str) -> Value {
+    serde_json::from_str(content).expect("Invalid fixture")
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+    (
+        status,
+        json!({
+            "statusCode": status,
+            "message": message,
+            "error": match status {
+                400 => "Bad Request",
+                401 => "Unauthorized",
+                _ => "Not Found",
+            },
+        }),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{core::EnsoError, metadata::token_filter::TokenFilter};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unauthorized() {
+        let server = MockServer::start();
+        let enso = Enso::builder()
+            .api_key("wrong")
+            .base_url(server.url())
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            enso.get_networks().await,
+            Err(EnsoError::Unauthorized { status: 401, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tokens_filter_and_pages() {
+        let server = MockServer::with_page_size(3);
+        let enso = server.enso();
+
+        let (_, tokens) = enso
+            .get_tokens(&[("chainId", "10"), ("page", "3")])
+            .await
+            .unwrap();
+
+        assert_eq!(tokens, vec!["0x8c6f28f2f1a3c87f0f938b96d27520d9751ec8d9"]);
+        assert_eq!(server.requests().len(), 1);
+
+        let filter = TokenFilter::new()
+            .chain_id(1)
+            .underlying_token("0xeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee")
+            .page_size(10);
+        let (meta, tokens) = enso.get_tokens(&filter).await.unwrap();
+        assert_eq!(meta.per_page, 10);
+        assert_eq!(tokens, vec!["0xae7ab96520de3a18e5e111b5eaab095312d7fe84"]);
+    }
+}
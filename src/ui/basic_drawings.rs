@@ -1,6 +1,9 @@
-use std::vec;
+use std::{collections::HashMap, vec};
 
-use enso::bundle::{actions::Action, core::ParamValue};
+use enso::{
+    bundle::{actions::Action, core::ParamValue},
+    metadata::{prices::Price, tokens::Token},
+};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -86,7 +89,7 @@ pub(crate) fn draw_value_list(
     title: &str,
     navigate: Navigable,
 ) {
-    fn get_value(value: &ParamValue) -> Vec<ListItem> {
+    fn get_value(value: &ParamValue) -> Vec<ListItem<'_>> {
         match value {
             ParamValue::ValueArray(values) => values
                 .iter()
@@ -117,14 +120,26 @@ pub(crate) fn draw_value_list(
 
 pub(crate) fn draw_tokens(
     f: &mut Frame,
-    tokens: &Option<Vec<String>>,
+    tokens: &Option<Vec<Token>>,
+    prices: &HashMap<(u32, String), Price>,
     area: Rect,
     navigate: Navigable,
 ) {
     let items = if let Some(tokens) = tokens.as_ref() {
         tokens
             .iter()
-            .map(|token| ListItem::new(token.as_str()))
+            .map(|token| {
+                let price = prices
+                    .get(&(token.chain_id, token.address.to_lowercase()))
+                    .map(|price| format!(" ${:.2}", price.price))
+                    .unwrap_or_default();
+                match &token.symbol {
+                    Some(symbol) => {
+                        ListItem::new(format!("{:<12} {}{}", symbol, token.address, price))
+                    }
+                    None => ListItem::new(format!("{}{}", token.address, price)),
+                }
+            })
             .collect::<Vec<ListItem>>()
     } else {
         vec![ListItem::new("Waiting tokens list...")]
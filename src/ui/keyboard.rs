@@ -74,15 +74,11 @@ pub fn draw_input(
 fn handle_input(value: &mut String, key_event: KeyEvent, input_type: &InputType) {
     match key_event {
         KeyEvent::Char(c) => match input_type {
-            InputType::Hex => {
-                if c.is_ascii_hexdigit() {
-                    value.push(c);
-                }
+            InputType::Hex if c.is_ascii_hexdigit() => {
+                value.push(c);
             }
-            InputType::Number => {
-                if c.is_ascii_digit() {
-                    value.push(c);
-                }
+            InputType::Number if c.is_ascii_digit() => {
+                value.push(c);
             }
             InputType::Text => {
                 value.push(c);
@@ -1,4 +1,4 @@
-use std::{io, rc::Rc, time::Duration};
+use std::{collections::HashMap, io, rc::Rc, time::Duration};
 
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
@@ -7,12 +7,15 @@ use crossterm::{
 };
 use enso::{
     bundle::{
+        action_matrix::ProtocolActionMatrix,
         actions::{Action, ACTION_CALL},
         core::ParamValue,
     },
     metadata::{
         networks::Network,
+        prices::Price,
         protocols::{Protocol, ENSO_PROTOCOL},
+        tokens::Token,
     },
 };
 use once_cell::sync::Lazy;
@@ -192,10 +195,12 @@ struct Data {
 
 #[derive(Default)]
 struct Cache {
-    tokens: Option<Vec<String>>,
+    tokens: Option<Vec<Token>>,
     protocols: Option<Vec<Protocol>>,
-    actions: Option<Vec<Action>>,
+    actions: ProtocolActionMatrix,
     networks: Option<Vec<Network>>,
+    chain_id: Option<u32>,
+    prices: HashMap<(u32, String), Price>,
 }
 
 pub async fn run(
@@ -226,6 +231,13 @@ pub async fn run(
             })?;
         }
         if let Some(msg) = msg {
+            if let UIRequest::SetNetwork(chain_id) = msg {
+                // The protocols and tokens depend on the network.
+                cache.chain_id = Some(chain_id);
+                cache.protocols = None;
+                cache.tokens = None;
+                cache.prices.clear();
+            }
             _ = ui_to_business_sender.send(msg).await;
         }
 
@@ -239,8 +251,8 @@ pub async fn run(
             Ok(Some(BusinessResponse::Protocols(p))) => {
                 cache.protocols = Some(p);
             }
-            Ok(Some(BusinessResponse::Actions(a))) => {
-                cache.actions = Some(a);
+            Ok(Some(BusinessResponse::ProtocolActions(slug, a))) => {
+                cache.actions.insert(slug, a);
             }
             Ok(Some(BusinessResponse::Tokens(t))) => {
                 cache.tokens = Some(t);
@@ -248,6 +260,13 @@ pub async fn run(
             Ok(Some(BusinessResponse::Networks(t))) => {
                 cache.networks = Some(t);
             }
+            // Prices fetched for a previous network are dropped.
+            Ok(Some(BusinessResponse::Prices(chain_id, p))) if cache.chain_id == Some(chain_id) => {
+                cache.prices.extend(
+                    p.into_iter()
+                        .map(|price| ((chain_id, price.address.to_lowercase()), price)),
+                );
+            }
             _ => {}
         }
     }
@@ -274,6 +293,8 @@ fn layout(
         tokens,
         actions,
         networks,
+        prices,
+        ..
     } = cache;
     let header = Layout::default()
         .direction(Direction::Vertical)
@@ -321,9 +342,8 @@ fn layout(
                 networks,
                 selected_network,
             );
-            return request.map(|r| {
+            return request.inspect(|_| {
                 *ui_state = UIState::BrowseTransactions;
-                r
             });
         }
         UIState::BrowseTransactions | UIState::BrowseParameters | UIState::BrowseValues => {
@@ -385,8 +405,10 @@ fn layout(
             );
             if let Some(state) = state {
                 let request = match state {
-                    UIState::ActionSelector { .. } if actions.is_none() => {
-                        Some(UIRequest::GetActions)
+                    UIState::ActionSelector { ref protocol, .. }
+                        if !actions.contains(&protocol.slug) =>
+                    {
+                        Some(UIRequest::GetProtocolActions(protocol.slug.clone()))
                     }
                     _ => None,
                 };
@@ -408,7 +430,7 @@ fn layout(
                     footer: footer[0],
                     key_event,
                 },
-                actions,
+                actions.actions(&protocol.slug),
                 *selected_action_type,
                 selected_action,
                 protocol,
@@ -428,6 +450,7 @@ fn layout(
                     key_event,
                 },
                 tokens,
+                prices,
                 selected_token,
             );
             if let Some(state) = state {
@@ -644,26 +667,24 @@ fn browse_transactions(h: Handle, ui_state: &mut UIState) -> Option<UIRequest> {
         (KeyEvent::Char('I') | KeyEvent::Char('i'), _) => {
             *ui_state = UIState::ActionTypeSelector(0);
         }
-        (KeyEvent::Char('E') | KeyEvent::Char('e'), _) => {
-            if !h.data.transactions.is_empty() {
-                let transactions = h.data.transactions.clone();
-                h.data.transactions.clear();
-                h.data.selected_transaction = 0;
-                h.data.selected_parameter = 0;
-                h.data.selected_value = 0;
-                *ui_state = UIState::BrowseTransactions;
-                return Some(UIRequest::SendBundle(transactions));
-            }
+        (KeyEvent::Char('E') | KeyEvent::Char('e'), _) if !h.data.transactions.is_empty() => {
+            let transactions = h.data.transactions.clone();
+            h.data.transactions.clear();
+            h.data.selected_transaction = 0;
+            h.data.selected_parameter = 0;
+            h.data.selected_value = 0;
+            *ui_state = UIState::BrowseTransactions;
+            return Some(UIRequest::SendBundle(transactions));
         }
-        (KeyEvent::Char('D') | KeyEvent::Char('d'), UIState::BrowseTransactions) => {
-            if !h.data.transactions.is_empty() {
-                h.data.transactions.remove(h.data.selected_transaction);
-                if h.data.selected_transaction >= h.data.transactions.len() {
-                    h.data.selected_transaction = h.data.transactions.len().saturating_sub(1);
-                }
-                h.data.selected_parameter = 0;
-                h.data.selected_value = 0;
+        (KeyEvent::Char('D') | KeyEvent::Char('d'), UIState::BrowseTransactions)
+            if !h.data.transactions.is_empty() =>
+        {
+            h.data.transactions.remove(h.data.selected_transaction);
+            if h.data.selected_transaction >= h.data.transactions.len() {
+                h.data.selected_transaction = h.data.transactions.len().saturating_sub(1);
             }
+            h.data.selected_parameter = 0;
+            h.data.selected_value = 0;
         }
         _ => {}
     };
@@ -720,7 +741,7 @@ fn handle_protocol_selection(
     let items = if let Some(protocols) = protocols {
         protocols
             .iter()
-            .map(|protocol| ListItem::new(protocol.slug.clone()))
+            .map(|protocol| ListItem::new(protocol.display_name().to_string()))
             .collect::<Vec<ListItem>>()
     } else {
         vec![ListItem::new("Waiting protocols list...")]
@@ -748,7 +769,7 @@ fn handle_protocol_selection(
 
 fn handle_action_selection(
     h: Handle,
-    actions: &Option<Vec<Action>>,
+    actions: Option<&[Action]>,
     selected_action_type: usize,
     selected_action: &mut usize,
     protocol: &Protocol,
@@ -777,7 +798,7 @@ fn handle_action_selection(
     h.f.render_widget(Block::default().borders(Borders::ALL), h.body[2]);
     match h.key_event {
         KeyEvent::Enter | KeyEvent::Right => {
-            if let Some(action) = actions.as_ref().and_then(|p| p.get(*selected_action)) {
+            if let Some(action) = actions.and_then(|p| p.get(*selected_action)) {
                 h.data.transactions.push((
                     action.clone(),
                     protocol.clone(),
@@ -797,7 +818,8 @@ fn handle_action_selection(
 
 fn handle_token_selection(
     h: Handle,
-    tokens: &Option<Vec<String>>,
+    tokens: &Option<Vec<Token>>,
+    prices: &HashMap<(u32, String), Price>,
     selected_token: &mut usize,
 ) -> Option<UIState> {
     h.f.render_widget(H_TOKEN_DESC.clone(), h.header);
@@ -831,7 +853,7 @@ fn handle_token_selection(
         Navigable::NotNavigable(h.data.selected_parameter),
     );
     let navigate = Navigable::Navigable(h.key_event, selected_token);
-    draw_tokens(h.f, tokens, h.body[2], navigate);
+    draw_tokens(h.f, tokens, prices, h.body[2], navigate);
     match h.key_event {
         KeyEvent::Enter => {
             if let Some(token) = tokens.as_ref().and_then(|t| t.get(*selected_token)) {
@@ -841,7 +863,7 @@ fn handle_token_selection(
                     .get_mut(h.data.selected_transaction)
                     .and_then(|tx| tx.2.get_mut(h.data.selected_parameter));
                 if let Some(param) = param {
-                    *param = ParamValue::Value(token.clone());
+                    *param = ParamValue::Value(token.address.clone());
                 }
                 Some(UIState::BrowseParameters)
             } else {
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{Endpoint, Enso, Result},
+    metadata::units::format_units,
+};
+
+/// The amount of a token held by a wallet.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balance {
+    /// The ID of the chain the token lives on.
+    pub chain_id: u32,
+    /// The address of the token.
+    pub token: String,
+    /// The raw on-chain amount, as a string of digits.
+    pub amount: String,
+    /// The number of decimals of the token.
+    pub decimals: u8,
+    /// The price of one token in USD, if known.
+    #[serde(default)]
+    pub price: Option<f64>,
+    /// The symbol of the token, e.g. `USDC`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// The name of the token, e.g. `USD Coin`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The URL of the logo of the token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+}
+
+impl Balance {
+    /// Returns the amount as a decimal number, e.g. `1.5` for `1500000` USDC.
+    pub fn formatted_amount(&self) -> Option<String> {
+        format_units(&self.amount, self.decimals)
+    }
+
+    /// Returns the value of the balance in USD, or `None` if the token has no
+    /// price.
+    pub fn usd_value(&self) -> Option<f64> {
+        let amount = self.formatted_amount()?.parse::<f64>().ok()?;
+        Some(amount * self.price?)
+    }
+
+    /// Returns `true` if the balance is empty, or priced below `min_usd`.
+    /// Balances without a price are never dust, since their value is unknown.
+    pub fn is_dust(&self, min_usd: f64) -> bool {
+        self.amount.trim_start_matches('0').is_empty()
+            || self.usd_value().is_some_and(|value| value < min_usd)
+    }
+}
+
+/// Returns the total value of `balances` in USD, ignoring the unpriced ones.
+///
+/// # Example
+///
+/// ```no_run
+/// # use enso::wallet::balances::total_usd;
+/// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+/// let balances = enso.get_balances(1, "0xd8da6bf26964af9d7eed9e03e53415d37aa96045").await?;
+/// println!("{:.2} USD", total_usd(&balances));
+/// # Ok(())
+/// # }
+/// ```
+pub fn total_usd(balances: &[Balance]) -> f64 {
+    balances.iter().filter_map(Balance::usd_value).sum()
+}
+
+/// Removes the dust from `balances`, see [`Balance::is_dust`].
+pub fn without_dust(balances: Vec<Balance>, min_usd: f64) -> Vec<Balance> {
+    balances
+        .into_iter()
+        .filter(|balance| !balance.is_dust(min_usd))
+        .collect()
+}
+
+impl Enso {
+    /// Retrieves the tokens held by `wallet` on the chain `chain_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - The ID of the chain.
+    /// * `wallet` - The address of the wallet, e.g. the `from_address` of a bundle.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a vector of `Balance` instances or an `EnsoError`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use enso::wallet::balances::without_dust;
+    /// # async fn example(enso: enso::core::Enso) -> enso::core::Result<()> {
+    /// let balances = enso.get_balances(1, "0xd8da6bf26964af9d7eed9e03e53415d37aa96045").await?;
+    /// for balance in without_dust(balances, 1.0) {
+    ///     println!("{:?} {:?}", balance.symbol, balance.formatted_amount());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(skip(self))]
+    pub async fn get_balances(&self, chain_id: u32, wallet: &str) -> Result<Vec<Balance>> {
+        let path = Endpoint::Balances.path();
+        let query = [
+            ("chainId".to_string(), chain_id.to_string()),
+            ("eoaAddress".to_string(), wallet.to_string()),
+            ("useEoa".to_string(), "true".to_string()),
+        ];
+        self.get_json(path, &query).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::MockServer;
+
+    use super::*;
+
+    const WALLET: &str = "0xd8da6bf26964af9d7eed9e03e53415d37aa96045";
+
+    #[tokio::test]
+    async fn test_get_balances() {
+        let server = MockServer::start();
+        let enso = server.enso();
+
+        let balances = enso.get_balances(1, WALLET).await.unwrap();
+        assert_eq!(balances.len(), 4);
+        assert_eq!(balances[1].formatted_amount().as_deref(), Some("2500"));
+        assert_eq!(balances[1].usd_value(), Some(2500.0));
+        assert_eq!(balances[3].usd_value(), None);
+        assert_eq!(server.requests()[0].query_param("eoaAddress"), Some(WALLET));
+
+        let total = total_usd(&balances);
+        assert!((total - (1.5 * 3015.42 + 2500.0 + 0.000001 * 3010.12)).abs() < 1e-6);
+
+        let kept = without_dust(balances, 0.01)
+            .into_iter()
+            .filter_map(|balance| balance.symbol)
+            .collect::<Vec<_>>();
+        assert_eq!(kept, vec!["ETH", "USDC", "UNI"]);
+    }
+}